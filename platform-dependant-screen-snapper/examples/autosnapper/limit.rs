@@ -0,0 +1,36 @@
+use async_trait::async_trait;
+use platform_dependant_screen_snapper::control::{ControlCommand, Controller};
+use remotia::traits::FrameProcessor;
+
+/// Stops the snapper once enough snapshots have been taken, as a shutdown command would
+pub(crate) struct SnapshotLimit {
+    taken: usize,
+    max_count: usize,
+
+    /// Controller of the snapper, whose pending snapshots are saved before it stops
+    controller: Controller,
+}
+
+impl SnapshotLimit {
+    pub(crate) fn new(max_count: usize, controller: Controller) -> Self {
+        Self {
+            taken: 0,
+            max_count,
+            controller,
+        }
+    }
+}
+
+#[async_trait]
+impl<F: Send + 'static> FrameProcessor<F> for SnapshotLimit {
+    async fn process(&mut self, frame_data: F) -> Option<F> {
+        self.taken += 1;
+
+        if self.taken == self.max_count {
+            log::info!("Took {} snapshots, stopping", self.taken);
+            self.controller.apply(ControlCommand::Shutdown);
+        }
+
+        Some(frame_data)
+    }
+}
@@ -0,0 +1,88 @@
+fn main() {
+    env_logger::init();
+
+    #[cfg(feature = "xcap")]
+    list_xcap_monitors();
+
+    #[cfg(feature = "wayshot")]
+    list_wayshot_outputs();
+}
+
+#[cfg(feature = "xcap")]
+fn list_xcap_monitors() {
+    use platform_dependant_screen_snapper::xcap_capturer::xcap_utils;
+    use xcap::Monitor;
+
+    let monitors = match Monitor::all() {
+        Ok(monitors) => monitors,
+        Err(err) => {
+            println!("XCap: unavailable ({err})");
+            return;
+        }
+    };
+
+    println!("XCap monitors:");
+    println!(
+        "{:<9} {:<24} {:<22} {:>6} {:>9}",
+        "INDEX", "NAME", "GEOMETRY", "SCALE", "ROTATION"
+    );
+
+    for (index, monitor) in monitors.iter().enumerate() {
+        let geometry =
+            xcap_utils::monitor_geometry(monitor).expect("Unable to read the monitor geometry");
+        let is_primary = monitor.is_primary().unwrap_or(false);
+
+        println!(
+            "{:<9} {:<24} {:<22} {:>6.2} {:>8}°{}",
+            format!("index:{index}"),
+            format!("name:{}", geometry.name),
+            format!(
+                "{}x{}{:+}{:+}",
+                geometry.width, geometry.height, geometry.x, geometry.y
+            ),
+            monitor.scale_factor().unwrap_or(1.0),
+            monitor.rotation().unwrap_or(0.0),
+            if is_primary { " (primary)" } else { "" }
+        );
+    }
+}
+
+#[cfg(feature = "wayshot")]
+fn list_wayshot_outputs() {
+    use platform_dependant_screen_snapper::wayshot_capturer::wayshot_utils;
+
+    let connection = match wayshot_utils::connect() {
+        Ok(connection) => connection,
+        Err(error) => {
+            println!("Wayshot: unavailable ({error:?})");
+            return;
+        }
+    };
+
+    println!("Wayshot outputs (geometry in captured pixels):");
+    println!(
+        "{:<16} {:<22} {:<14} DESCRIPTION",
+        "NAME", "GEOMETRY", "PHYSICAL"
+    );
+
+    let outputs = connection.get_all_outputs();
+    let scale = wayshot_utils::display_scale(outputs);
+
+    for output in outputs {
+        let geometry = wayshot_utils::output_geometry(output, scale);
+
+        println!(
+            "{:<16} {:<22} {:<14} {}",
+            geometry.name,
+            format!(
+                "{}x{}{:+}{:+}",
+                geometry.width, geometry.height, geometry.x, geometry.y
+            ),
+            format!(
+                "{}x{}",
+                output.physical_size.width, output.physical_size.height
+            ),
+            output.description
+        );
+    }
+}
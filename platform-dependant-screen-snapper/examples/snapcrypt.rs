@@ -0,0 +1,225 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use clap::{Parser, Subcommand};
+use platform_dependant_screen_snapper::encryption::{ENCRYPTED_EXTENSION, EncryptionKey, EnvelopeHeader, Keyring};
+
+/// Manages the keys of the autosnapper's --encrypt-key option, and decrypts its snapshots.
+/// To rotate keys, generate a new one and restart the autosnapper with it: the snapshots
+/// taken before are decrypted by passing the former keys along with the new one.
+#[derive(Parser, Debug)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Write a new random key to a file, readable by its owner only
+    Keygen { path: PathBuf },
+
+    /// Print which key encrypted the given snapshots
+    Inspect {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+
+    /// Decrypt the given snapshots, and those found in the given directories
+    Decrypt {
+        /// Key file, repeated for the snapshots encrypted with former keys
+        #[arg(long)]
+        key: Vec<PathBuf>,
+
+        /// File holding a passphrase, repeated for the snapshots encrypted with former ones
+        #[arg(long)]
+        passphrase_file: Vec<PathBuf>,
+
+        /// Where the decrypted snapshots are written, next to the encrypted ones by default
+        #[arg(long)]
+        output_dir: Option<PathBuf>,
+
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+}
+
+fn main() {
+    env_logger::init();
+
+    let args = Args::parse();
+
+    let succeeded = match args.command {
+        Command::Keygen { path } => keygen(&path),
+        Command::Inspect { files } => {
+            let mut succeeded = true;
+            for file in files {
+                succeeded &= inspect(&file);
+            }
+            succeeded
+        }
+        Command::Decrypt {
+            key,
+            passphrase_file,
+            output_dir,
+            paths,
+        } => decrypt(&key, &passphrase_file, output_dir.as_deref(), &paths),
+    };
+
+    if !succeeded {
+        std::process::exit(1);
+    }
+}
+
+fn keygen(path: &Path) -> bool {
+    match EncryptionKey::generate_key_file(path) {
+        Ok(key) => {
+            println!("Wrote key {} to {}", key.id(), path.display());
+            true
+        }
+        Err(error) => {
+            eprintln!("Unable to write {}: {:?}", path.display(), error);
+            false
+        }
+    }
+}
+
+fn inspect(file: &Path) -> bool {
+    let header = fs::read(file)
+        .map_err(|err| err.to_string())
+        .and_then(|data| {
+            EnvelopeHeader::parse(&data)
+                .map(|(header, _)| header)
+                .map_err(|error| format!("{error:?}"))
+        });
+
+    match header {
+        Ok(header) => {
+            match header.kdf {
+                Some(kdf) => println!(
+                    "{}: key {}, derived from a passphrase with Argon2id (m={} KiB, t={}, p={})",
+                    file.display(),
+                    header.key_id,
+                    kdf.memory_kib,
+                    kdf.iterations,
+                    kdf.parallelism
+                ),
+                None => println!("{}: key {}, from a key file", file.display(), header.key_id),
+            }
+            true
+        }
+        Err(err) => {
+            eprintln!("Unable to inspect {}: {err}", file.display());
+            false
+        }
+    }
+}
+
+fn decrypt(
+    key_files: &[PathBuf],
+    passphrase_files: &[PathBuf],
+    output_dir: Option<&Path>,
+    paths: &[PathBuf],
+) -> bool {
+    let mut keyring = Keyring::new();
+
+    for path in key_files {
+        match EncryptionKey::from_key_file(path) {
+            Ok(key) => keyring.add_key(key),
+            Err(error) => {
+                eprintln!("Unable to read {}: {:?}", path.display(), error);
+                return false;
+            }
+        }
+    }
+
+    for path in passphrase_files {
+        match fs::read_to_string(path) {
+            Ok(passphrase) => keyring.add_passphrase(passphrase.trim_end_matches(['\r', '\n'])),
+            Err(err) => {
+                eprintln!("Unable to read {}: {err}", path.display());
+                return false;
+            }
+        }
+    }
+
+    // Pairs of encrypted snapshots and where to write them
+    let mut snapshots = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let mut files = Vec::new();
+            collect_encrypted(path, &mut files);
+
+            snapshots.extend(files.into_iter().map(|file| {
+                let output = match (output_dir, file.strip_prefix(path)) {
+                    (Some(output_dir), Ok(relative)) => output_dir.join(relative),
+                    _ => file.clone(),
+                };
+                (file, output)
+            }));
+        } else {
+            let output = match (output_dir, path.file_name()) {
+                (Some(output_dir), Some(name)) => output_dir.join(name),
+                _ => path.clone(),
+            };
+            snapshots.push((path.clone(), output));
+        }
+    }
+
+    let mut succeeded = true;
+    for (input, output) in snapshots {
+        if output
+            .extension()
+            .is_none_or(|extension| extension != ENCRYPTED_EXTENSION)
+        {
+            eprintln!(
+                "Skipping {}, not ending with .{ENCRYPTED_EXTENSION}",
+                input.display()
+            );
+            succeeded = false;
+            continue;
+        }
+
+        let output = output.with_extension("");
+        let result = fs::read(&input)
+            .map_err(|err| err.to_string())
+            .and_then(|data| keyring.decrypt(&data).map_err(|error| format!("{error:?}")))
+            .and_then(|plaintext| {
+                output
+                    .parent()
+                    .map_or(Ok(()), fs::create_dir_all)
+                    .and_then(|_| fs::write(&output, plaintext))
+                    .map_err(|err| err.to_string())
+            });
+
+        match result {
+            Ok(()) => println!("{} -> {}", input.display(), output.display()),
+            Err(err) => {
+                eprintln!("Unable to decrypt {}: {err}", input.display());
+                succeeded = false;
+            }
+        }
+    }
+
+    succeeded
+}
+
+/// Encrypted snapshots under a directory, recursively
+fn collect_encrypted(directory: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(directory) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_encrypted(&path, files);
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == ENCRYPTED_EXTENSION)
+        {
+            files.push(path);
+        }
+    }
+}
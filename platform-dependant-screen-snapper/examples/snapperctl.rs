@@ -0,0 +1,54 @@
+#[cfg(unix)]
+use std::path::PathBuf;
+
+#[cfg(unix)]
+use clap::Parser;
+#[cfg(unix)]
+use platform_dependant_screen_snapper::control::{self, ControlCommand};
+
+/// Sends a command to an autosnapper started with --control-socket
+#[cfg(unix)]
+#[derive(Parser, Debug)]
+struct Args {
+    /// Control socket of the autosnapper
+    #[arg(long)]
+    socket: PathBuf,
+
+    /// pause, resume, snap-now, set-interval <MS>, status or shutdown
+    #[arg(required = true, num_args = 1..)]
+    command: Vec<String>,
+}
+
+#[cfg(unix)]
+#[tokio::main]
+async fn main() {
+    env_logger::init();
+
+    let args = Args::parse();
+
+    let command: ControlCommand = match args.command.join(" ").parse() {
+        Ok(command) => command,
+        Err(err) => {
+            eprintln!("{err}");
+            std::process::exit(2);
+        }
+    };
+
+    match control::send_command(&args.socket, command).await {
+        Ok(reply) => {
+            println!("{reply}");
+            if !reply.starts_with("ok") {
+                std::process::exit(1);
+            }
+        }
+        Err(err) => {
+            eprintln!("Unable to reach {}: {err}", args.socket.display());
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn main() {
+    eprintln!("The control socket is only available on Unix");
+}
@@ -0,0 +1,218 @@
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    str::FromStr,
+};
+
+use async_trait::async_trait;
+use bon::Builder;
+use image::{DynamicImage, RgbImage};
+use remotia::{
+    buffers::BytesMut,
+    traits::{FrameError, FrameProcessor, FrameProperties, PullableFrameProperties},
+};
+
+use crate::{
+    error::Error,
+    geometry::GeometryChange,
+    pixel_format::{FrameFormat, PixelFormat},
+};
+
+pub const DEFAULT_CHANGE_THRESHOLD: f64 = 0.01;
+
+/// How two frames are compared, all metrics scoring from 0 (identical) to 1
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChangeMetric {
+    /// Hash of the pixels, scoring 1 on any change
+    #[default]
+    ExactHash,
+    /// Share of the pixels whose color changed
+    PixelRatio,
+    /// Share of the bits differing between the difference hashes of the frames,
+    /// which ignores small or scattered changes
+    PerceptualHash,
+}
+
+impl FromStr for ChangeMetric {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "exact" => Ok(Self::ExactHash),
+            "pixels" => Ok(Self::PixelRatio),
+            "phash" => Ok(Self::PerceptualHash),
+            _ => Err(format!("Unknown change metric: {value}")),
+        }
+    }
+}
+
+/// How much a frame differs from the last kept one
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct ChangeScore(pub f64);
+
+enum Reference {
+    Hash(u64),
+    Pixels(FrameFormat, Vec<u8>),
+}
+
+/// Drops the frames too similar to the last kept one, reporting them as unchanged
+#[derive(Builder)]
+pub struct ChangeDetector<K> {
+    #[builder(skip)]
+    reference: Option<Reference>,
+
+    buffer_key: K,
+
+    #[builder(default)]
+    metric: ChangeMetric,
+
+    /// Frames scoring below the threshold are dropped
+    #[builder(default = DEFAULT_CHANGE_THRESHOLD)]
+    threshold: f64,
+}
+
+impl<K> ChangeDetector<K> {
+    fn fingerprint(&self, format: FrameFormat, buffer: &[u8]) -> Reference {
+        match self.metric {
+            ChangeMetric::ExactHash => {
+                let mut hasher = DefaultHasher::new();
+                (format.pixel_format, format.width, format.height).hash(&mut hasher);
+                format.rows(buffer).for_each(|row| row.hash(&mut hasher));
+
+                Reference::Hash(hasher.finish())
+            }
+            ChangeMetric::PixelRatio => Reference::Pixels(format, buffer.to_vec()),
+            ChangeMetric::PerceptualHash => Reference::Hash(difference_hash(format, buffer)),
+        }
+    }
+
+    fn score(&self, candidate: &Reference) -> f64 {
+        match (&self.reference, candidate) {
+            (Some(Reference::Hash(reference)), Reference::Hash(hash)) => match self.metric {
+                ChangeMetric::PerceptualHash => (reference ^ hash).count_ones() as f64 / 64.0,
+                _ => (reference != hash) as u8 as f64,
+            },
+            (
+                Some(Reference::Pixels(reference_format, reference)),
+                Reference::Pixels(format, buffer),
+            ) if (reference_format.width, reference_format.height)
+                == (format.width, format.height) =>
+            {
+                changed_pixels_ratio(*reference_format, reference, *format, buffer)
+            }
+            // Nothing to compare against, or a different frame size
+            _ => 1.0,
+        }
+    }
+
+    fn check(&mut self, format: FrameFormat, buffer: &[u8]) -> Result<f64, Error> {
+        if !format.fits(buffer) {
+            return Err(Error::SizeMismatch.logged(format!(
+                "{} bytes do not match {:?}",
+                buffer.len(),
+                format
+            )));
+        }
+
+        let candidate = self.fingerprint(format, buffer);
+        let score = self.score(&candidate);
+
+        if score >= self.threshold {
+            self.reference = Some(candidate);
+        }
+
+        Ok(score)
+    }
+}
+
+fn changed_pixels_ratio(
+    reference_format: FrameFormat,
+    reference: &[u8],
+    format: FrameFormat,
+    buffer: &[u8],
+) -> f64 {
+    let reference_bpp = reference_format.pixel_format.bytes_per_pixel();
+    let bpp = format.pixel_format.bytes_per_pixel();
+
+    let mut changed = 0;
+    for (reference_row, row) in reference_format.rows(reference).zip(format.rows(buffer)) {
+        let pixels = reference_row
+            .chunks_exact(reference_bpp)
+            .zip(row.chunks_exact(bpp));
+
+        changed += pixels
+            .filter(|(reference_pixel, pixel)| {
+                reference_format.pixel_format.read(reference_pixel)
+                    != format.pixel_format.read(pixel)
+            })
+            .count();
+    }
+
+    changed as f64 / (format.width as f64 * format.height as f64).max(1.0)
+}
+
+/// 64 bits hash telling whether each cell of a 9x8 grayscale thumbnail is brighter than the next
+fn difference_hash(format: FrameFormat, buffer: &[u8]) -> u64 {
+    let pixels = format.convert(buffer, PixelFormat::Rgb8);
+    let Some(image) = RgbImage::from_raw(format.width, format.height, pixels) else {
+        return 0;
+    };
+
+    let thumbnail = DynamicImage::ImageRgb8(image)
+        .thumbnail_exact(9, 8)
+        .into_luma8();
+
+    (0..8)
+        .flat_map(|y| (0..8).map(move |x| (x, y)))
+        .fold(0, |hash, (x, y)| {
+            let brighter = thumbnail.get_pixel(x, y)[0] > thumbnail.get_pixel(x + 1, y)[0];
+            (hash << 1) | brighter as u64
+        })
+}
+
+#[async_trait]
+impl<K, F> FrameProcessor<F> for ChangeDetector<K>
+where
+    F: Send + 'static,
+    K: Send + Copy,
+    F: PullableFrameProperties<K, BytesMut>
+        + FrameProperties<K, FrameFormat>
+        + FrameProperties<K, ChangeScore>
+        + FrameProperties<K, GeometryChange>
+        + FrameError<Error>,
+{
+    async fn process(&mut self, mut frame_data: F) -> Option<F> {
+        let Some(format) = FrameProperties::<K, FrameFormat>::get(&frame_data, &self.buffer_key)
+        else {
+            frame_data.report_error(Error::MissingFormat);
+            return Some(frame_data);
+        };
+
+        // Kept whatever the metric, so that the processors downstream see the change
+        if FrameProperties::<K, GeometryChange>::get(&frame_data, &self.buffer_key).is_some() {
+            log::debug!("Capture geometry changed, discarding the reference frame");
+            self.reference = None;
+        }
+
+        let Some(buffer) = frame_data.pull(&self.buffer_key) else {
+            frame_data.report_error(Error::MissingBuffer);
+            return Some(frame_data);
+        };
+
+        let result = self.check(format, &buffer);
+        frame_data.push(self.buffer_key, buffer);
+
+        match result {
+            Ok(score) => {
+                frame_data.set(self.buffer_key, ChangeScore(score));
+
+                if score < self.threshold {
+                    log::debug!("Unchanged frame (score {score:.4}), dropping");
+                    frame_data.report_error(Error::Unchanged);
+                }
+            }
+            Err(error) => frame_data.report_error(error),
+        }
+
+        Some(frame_data)
+    }
+}
@@ -0,0 +1,370 @@
+use std::{
+    fmt::Display,
+    str::FromStr,
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use remotia::traits::FrameProcessor;
+use tokio::{
+    sync::Notify,
+    time::{self, Instant},
+};
+
+#[cfg(unix)]
+pub use socket::{ControlServer, send_command};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ControlCommand {
+    /// Stop the periodic snapshots, on-demand ones being still taken
+    Pause,
+    Resume,
+    /// Take a snapshot right away, even while paused
+    SnapNow,
+    /// Milliseconds between two periodic snapshots
+    SetInterval(u64),
+    Status,
+    Shutdown,
+}
+
+impl Display for ControlCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pause => write!(f, "pause"),
+            Self::Resume => write!(f, "resume"),
+            Self::SnapNow => write!(f, "snap-now"),
+            Self::SetInterval(interval) => write!(f, "set-interval {interval}"),
+            Self::Status => write!(f, "status"),
+            Self::Shutdown => write!(f, "shutdown"),
+        }
+    }
+}
+
+/// Parses "pause", "resume", "snap-now", "set-interval <ms>", "status" or "shutdown"
+impl FromStr for ControlCommand {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = value.split_whitespace().collect();
+
+        match words.as_slice() {
+            ["pause"] => Ok(Self::Pause),
+            ["resume"] => Ok(Self::Resume),
+            ["snap-now"] => Ok(Self::SnapNow),
+            ["set-interval", interval] => interval
+                .parse()
+                .ok()
+                .filter(|interval| *interval > 0)
+                .map(Self::SetInterval)
+                .ok_or_else(|| format!("Invalid interval: {interval}")),
+            ["status"] => Ok(Self::Status),
+            ["shutdown"] => Ok(Self::Shutdown),
+            _ => Err(format!("Unknown command: {value}")),
+        }
+    }
+}
+
+struct State {
+    paused: bool,
+    interval: Duration,
+    /// Snapshots requested on demand but not triggered yet
+    requested: usize,
+    triggered: u64,
+    /// Triggered frames done with, whether saved or dropped
+    settled: u64,
+    shutdown: bool,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    /// Wakes up the trigger and the shutdown waiters when a command is applied
+    /// or a frame is settled
+    changed: Notify,
+}
+
+/// State of a snapper driven by commands, shared by its trigger and its control socket
+#[derive(Clone)]
+pub struct Controller {
+    shared: Arc<Shared>,
+}
+
+impl Controller {
+    /// Controller of a snapper taking a snapshot every `interval` milliseconds
+    pub fn new(interval: u64) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                state: Mutex::new(State {
+                    paused: false,
+                    interval: Duration::from_millis(interval),
+                    requested: 0,
+                    triggered: 0,
+                    settled: 0,
+                    shutdown: false,
+                }),
+                changed: Notify::new(),
+            }),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.shared
+            .state
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Applies a command, returning a description of the outcome
+    pub fn apply(&self, command: ControlCommand) -> String {
+        let reply = {
+            let mut state = self.state();
+            match command {
+                ControlCommand::Pause => {
+                    state.paused = true;
+                    "paused".to_string()
+                }
+                ControlCommand::Resume => {
+                    state.paused = false;
+                    "resumed".to_string()
+                }
+                ControlCommand::SnapNow => {
+                    state.requested += 1;
+                    "snapshot requested".to_string()
+                }
+                ControlCommand::SetInterval(interval) => {
+                    state.interval = Duration::from_millis(interval);
+                    format!("interval set to {interval} ms")
+                }
+                ControlCommand::Status => format!(
+                    "{}, interval {} ms, {} snapshots triggered, {} requested",
+                    if state.paused { "paused" } else { "running" },
+                    state.interval.as_millis(),
+                    state.triggered,
+                    state.requested
+                ),
+                ControlCommand::Shutdown => {
+                    state.shutdown = true;
+                    "shutting down".to_string()
+                }
+            }
+        };
+
+        log::info!("Applied {command}: {reply}");
+        self.shared.changed.notify_waiters();
+
+        reply
+    }
+
+    /// Waits until a shutdown is requested and the frames triggered before it are settled
+    pub async fn drained(&self) {
+        loop {
+            // Registered before checking the state, so that no wake-up is missed
+            let changed = self.shared.changed.notified();
+            {
+                let state = self.state();
+                if state.shutdown && state.settled >= state.triggered {
+                    return;
+                }
+            }
+
+            changed.await;
+        }
+    }
+}
+
+/// Starts frames periodically, like a ticker, and whenever a snapshot is requested.
+/// Periodic frames are held back while paused, and no frame is started after a shutdown.
+pub struct Trigger {
+    controller: Controller,
+    last_tick: Option<Instant>,
+}
+
+impl Trigger {
+    pub fn new(controller: Controller) -> Self {
+        Self {
+            controller,
+            last_tick: None,
+        }
+    }
+}
+
+#[async_trait]
+impl<F: Send + 'static> FrameProcessor<F> for Trigger {
+    async fn process(&mut self, frame_data: F) -> Option<F> {
+        loop {
+            let changed = self.controller.shared.changed.notified();
+
+            let next_tick = {
+                let mut state = self.controller.state();
+                let now = Instant::now();
+
+                if state.shutdown {
+                    None
+                } else if state.requested > 0 {
+                    state.requested -= 1;
+                    state.triggered += 1;
+                    return Some(frame_data);
+                } else if state.paused {
+                    None
+                } else {
+                    // Follows interval changes, counting from the last periodic frame
+                    let next_tick = self.last_tick.map_or(now, |tick| tick + state.interval);
+                    if next_tick <= now {
+                        self.last_tick = Some(now);
+                        state.triggered += 1;
+                        return Some(frame_data);
+                    }
+
+                    Some(next_tick)
+                }
+            };
+
+            match next_tick {
+                Some(next_tick) => {
+                    tokio::select! {
+                        _ = time::sleep_until(next_tick) => {}
+                        _ = changed => {}
+                    }
+                }
+                None => changed.await,
+            }
+        }
+    }
+}
+
+/// Settles the frames started by a trigger of the controller, to be appended where they
+/// leave the pipelines, e.g. once saved and in the error pipeline, so that shutting down
+/// waits for the snapshots in progress
+pub struct Settle {
+    controller: Controller,
+}
+
+impl Settle {
+    pub fn new(controller: Controller) -> Self {
+        Self { controller }
+    }
+}
+
+#[async_trait]
+impl<F: Send + 'static> FrameProcessor<F> for Settle {
+    async fn process(&mut self, frame_data: F) -> Option<F> {
+        self.controller.state().settled += 1;
+        self.controller.shared.changed.notify_waiters();
+
+        Some(frame_data)
+    }
+}
+
+#[cfg(unix)]
+mod socket {
+    use std::{
+        io,
+        path::{Path, PathBuf},
+    };
+
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::{UnixListener, UnixStream},
+    };
+
+    use super::{ControlCommand, Controller};
+    use crate::error::Error;
+
+    /// Listens for commands on a Unix domain socket. Each line holds a command,
+    /// answered by a line starting with "ok" or "error".
+    pub struct ControlServer {
+        listener: UnixListener,
+        path: PathBuf,
+        controller: Controller,
+    }
+
+    impl ControlServer {
+        /// Binds the socket, replacing any stale one left behind by a previous snapper
+        pub fn bind(path: impl Into<PathBuf>, controller: Controller) -> Result<Self, Error> {
+            let path = path.into();
+
+            if std::os::unix::net::UnixStream::connect(&path).is_ok() {
+                return Err(Error::ControlFailed.logged(format!(
+                    "another snapper is listening on {}",
+                    path.display()
+                )));
+            }
+
+            if path.exists() {
+                std::fs::remove_file(&path).map_err(|err| Error::ControlFailed.logged(err))?;
+            }
+
+            let listener =
+                UnixListener::bind(&path).map_err(|err| Error::ControlFailed.logged(err))?;
+            log::info!("Listening for commands on {}", path.display());
+
+            Ok(Self {
+                listener,
+                path,
+                controller,
+            })
+        }
+
+        pub fn controller(&self) -> &Controller {
+            &self.controller
+        }
+
+        /// Serves the clients until dropped
+        pub async fn run(self) {
+            loop {
+                match self.listener.accept().await {
+                    Ok((stream, _)) => {
+                        tokio::spawn(serve(stream, self.controller.clone()));
+                    }
+                    Err(err) => log::warn!("Unable to accept a control connection: {err}"),
+                }
+            }
+        }
+    }
+
+    impl Drop for ControlServer {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+
+    async fn serve(stream: UnixStream, controller: Controller) {
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+
+        loop {
+            let line = match lines.next_line().await {
+                Ok(Some(line)) => line,
+                Ok(None) => return,
+                Err(err) => {
+                    log::warn!("Unable to read a control command: {err}");
+                    return;
+                }
+            };
+
+            let reply = match line.parse::<ControlCommand>() {
+                Ok(command) => format!("ok {}\n", controller.apply(command)),
+                Err(err) => format!("error {err}\n"),
+            };
+
+            if let Err(err) = writer.write_all(reply.as_bytes()).await {
+                log::warn!("Unable to reply to a control command: {err}");
+                return;
+            }
+        }
+    }
+
+    /// Sends a command to the snapper listening on the socket, returning its reply
+    pub async fn send_command(path: &Path, command: ControlCommand) -> io::Result<String> {
+        let stream = UnixStream::connect(path).await?;
+        let (reader, mut writer) = stream.into_split();
+
+        writer.write_all(format!("{command}\n").as_bytes()).await?;
+
+        BufReader::new(reader)
+            .lines()
+            .next_line()
+            .await?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "no reply"))
+    }
+}
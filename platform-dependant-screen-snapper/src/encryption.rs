@@ -0,0 +1,358 @@
+use std::{
+    fmt::Display,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::{
+    AeadCore, KeyInit, XChaCha20Poly1305, XNonce,
+    aead::{Aead, OsRng, Payload, rand_core::RngCore},
+};
+use sha2::{Digest, Sha256};
+
+use crate::error::Error;
+
+/// Appended to the names of the encrypted snapshots, e.g. "1.png.enc"
+pub const ENCRYPTED_EXTENSION: &str = "enc";
+
+const MAGIC: &[u8; 8] = b"SNAPCRYP";
+const VERSION: u8 = 1;
+const CIPHER_XCHACHA20_POLY1305: u8 = 1;
+const KDF_NONE: u8 = 0;
+const KDF_ARGON2ID: u8 = 1;
+
+const KEY_SIZE: usize = 32;
+const SALT_SIZE: usize = 16;
+const NONCE_SIZE: usize = 24;
+
+/// Highest costs accepted from a header, so that a forged one can neither exhaust the memory
+/// nor keep the key derivation running for hours
+const MAX_MEMORY_KIB: u32 = 1 << 20;
+const MAX_ITERATIONS: u32 = 16;
+const MAX_PARALLELISM: u32 = 16;
+
+/// Extension of the encrypted snapshots of the given format extension, e.g. "png.enc"
+pub fn encrypted_extension(extension: &str) -> String {
+    format!("{extension}.{ENCRYPTED_EXTENSION}")
+}
+
+/// Identifies the key a snapshot was encrypted with, without revealing it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyId([u8; 8]);
+
+impl KeyId {
+    fn of(key: &[u8]) -> Self {
+        let digest = Sha256::new()
+            .chain_update(b"screen-snapper key id")
+            .chain_update(key)
+            .finalize();
+
+        let mut id = [0; 8];
+        id.copy_from_slice(&digest[..8]);
+        Self(id)
+    }
+}
+
+impl Display for KeyId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
+
+/// Parameters deriving a key from a passphrase with Argon2id
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+    pub salt: [u8; SALT_SIZE],
+}
+
+impl KdfParams {
+    fn generate() -> Self {
+        let mut salt = [0; SALT_SIZE];
+        OsRng.fill_bytes(&mut salt);
+
+        Self {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+            salt,
+        }
+    }
+
+    fn derive(&self, passphrase: &str) -> Result<[u8; KEY_SIZE], Error> {
+        if self.memory_kib > MAX_MEMORY_KIB
+            || self.iterations > MAX_ITERATIONS
+            || self.parallelism > MAX_PARALLELISM
+        {
+            return Err(Error::InvalidKey.logged(format!(
+                "the key derivation asks for {} KiB of memory, {} iterations and {} lanes",
+                self.memory_kib, self.iterations, self.parallelism
+            )));
+        }
+
+        let params = Params::new(
+            self.memory_kib,
+            self.iterations,
+            self.parallelism,
+            Some(KEY_SIZE),
+        )
+        .map_err(|err| Error::InvalidKey.logged(err))?;
+
+        let mut key = [0; KEY_SIZE];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &self.salt, &mut key)
+            .map_err(|err| Error::InvalidKey.logged(err))?;
+
+        Ok(key)
+    }
+}
+
+/// Leading part of an encrypted snapshot, describing how to decrypt it.
+/// It is authenticated along with the snapshot, so that it cannot be altered either.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EnvelopeHeader {
+    pub key_id: KeyId,
+    /// Set if the key is derived from a passphrase
+    pub kdf: Option<KdfParams>,
+    nonce: [u8; NONCE_SIZE],
+}
+
+impl EnvelopeHeader {
+    fn to_bytes(self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(64);
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.push(CIPHER_XCHACHA20_POLY1305);
+
+        match self.kdf {
+            None => bytes.push(KDF_NONE),
+            Some(kdf) => {
+                bytes.push(KDF_ARGON2ID);
+                bytes.extend_from_slice(&kdf.memory_kib.to_le_bytes());
+                bytes.extend_from_slice(&kdf.iterations.to_le_bytes());
+                bytes.extend_from_slice(&kdf.parallelism.to_le_bytes());
+                bytes.extend_from_slice(&kdf.salt);
+            }
+        }
+
+        bytes.extend_from_slice(&self.key_id.0);
+        bytes.extend_from_slice(&self.nonce);
+        bytes
+    }
+
+    /// Parses the header of an encrypted snapshot, returning it with its length
+    pub fn parse(data: &[u8]) -> Result<(Self, usize), Error> {
+        let mut reader = HeaderReader { data, offset: 0 };
+
+        if reader.take::<8>()? != *MAGIC {
+            return Err(Error::DecryptFailed.logged("not an encrypted snapshot"));
+        }
+
+        let [version] = reader.take()?;
+        let [cipher] = reader.take()?;
+        if version != VERSION || cipher != CIPHER_XCHACHA20_POLY1305 {
+            return Err(Error::DecryptFailed
+                .logged(format!("unsupported version {version} or cipher {cipher}")));
+        }
+
+        let kdf = match reader.take()? {
+            [KDF_NONE] => None,
+            [KDF_ARGON2ID] => Some(KdfParams {
+                memory_kib: u32::from_le_bytes(reader.take()?),
+                iterations: u32::from_le_bytes(reader.take()?),
+                parallelism: u32::from_le_bytes(reader.take()?),
+                salt: reader.take()?,
+            }),
+            [kdf] => {
+                return Err(
+                    Error::DecryptFailed.logged(format!("unsupported key derivation {kdf}"))
+                );
+            }
+        };
+
+        let header = Self {
+            key_id: KeyId(reader.take()?),
+            kdf,
+            nonce: reader.take()?,
+        };
+
+        Ok((header, reader.offset))
+    }
+}
+
+struct HeaderReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl HeaderReader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        let bytes = self
+            .data
+            .get(self.offset..self.offset + N)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| Error::DecryptFailed.logged("truncated header"))?;
+
+        self.offset += N;
+        Ok(bytes)
+    }
+}
+
+/// Encrypts snapshots with XChaCha20-Poly1305, under a key read from a key file
+/// or derived from a passphrase
+#[derive(Clone)]
+pub struct EncryptionKey {
+    id: KeyId,
+    kdf: Option<KdfParams>,
+    cipher: XChaCha20Poly1305,
+}
+
+impl EncryptionKey {
+    fn new(key: [u8; KEY_SIZE], kdf: Option<KdfParams>) -> Self {
+        Self {
+            id: KeyId::of(&key),
+            kdf,
+            cipher: XChaCha20Poly1305::new(&key.into()),
+        }
+    }
+
+    /// Reads a key file, holding the key as hexadecimal
+    pub fn from_key_file(path: &Path) -> Result<Self, Error> {
+        let contents = fs::read_to_string(path).map_err(|err| Error::InvalidKey.logged(err))?;
+
+        let key = hex::decode(contents.trim())
+            .ok()
+            .and_then(|key| <[u8; KEY_SIZE]>::try_from(key).ok())
+            .ok_or_else(|| {
+                Error::InvalidKey.logged(format!(
+                    "{} does not hold {KEY_SIZE} hexadecimal bytes",
+                    path.display()
+                ))
+            })?;
+
+        Ok(Self::new(key, None))
+    }
+
+    /// Derives a key from a passphrase, with a new random salt
+    pub fn from_passphrase(passphrase: &str) -> Result<Self, Error> {
+        let kdf = KdfParams::generate();
+        Ok(Self::new(kdf.derive(passphrase)?, Some(kdf)))
+    }
+
+    /// Writes a new random key to a file, which must not exist yet
+    pub fn generate_key_file(path: &Path) -> Result<Self, Error> {
+        let key: [u8; KEY_SIZE] = XChaCha20Poly1305::generate_key(&mut OsRng).into();
+
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        options
+            .open(path)
+            .and_then(|mut file| writeln!(file, "{}", hex::encode(key)))
+            .map_err(|err| Error::InvalidKey.logged(err))?;
+
+        Ok(Self::new(key, None))
+    }
+
+    pub fn id(&self) -> KeyId {
+        self.id
+    }
+
+    /// Encrypts a snapshot under a new nonce, prepending the header
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+        let header = EnvelopeHeader {
+            key_id: self.id,
+            kdf: self.kdf,
+            nonce: XChaCha20Poly1305::generate_nonce(&mut OsRng).into(),
+        };
+        let mut data = header.to_bytes();
+
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                XNonce::from_slice(&header.nonce),
+                Payload {
+                    msg: plaintext,
+                    aad: &data,
+                },
+            )
+            .map_err(|err| Error::EncryptFailed.logged(err))?;
+
+        data.extend_from_slice(&ciphertext);
+        Ok(data)
+    }
+
+    fn decrypt(
+        &self,
+        header: &EnvelopeHeader,
+        data: &[u8],
+        length: usize,
+    ) -> Result<Vec<u8>, Error> {
+        self.cipher
+            .decrypt(
+                XNonce::from_slice(&header.nonce),
+                Payload {
+                    msg: &data[length..],
+                    aad: &data[..length],
+                },
+            )
+            .map_err(|_| Error::DecryptFailed.logged("the snapshot was altered or corrupted"))
+    }
+}
+
+/// Keys and passphrases snapshots may have been encrypted with, e.g. the current one
+/// and those it replaced, so that rotating keys keeps the older snapshots readable
+#[derive(Default)]
+pub struct Keyring {
+    keys: Vec<EncryptionKey>,
+    passphrases: Vec<String>,
+}
+
+impl Keyring {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_key(&mut self, key: EncryptionKey) {
+        self.keys.push(key);
+    }
+
+    pub fn add_passphrase(&mut self, passphrase: impl Into<String>) {
+        self.passphrases.push(passphrase.into());
+    }
+
+    /// Decrypts a snapshot, with the key whose identifier is found in its header
+    pub fn decrypt(&mut self, data: &[u8]) -> Result<Vec<u8>, Error> {
+        let (header, length) = EnvelopeHeader::parse(data)?;
+
+        let key = match self.keys.iter().find(|key| key.id == header.key_id) {
+            Some(key) => key.clone(),
+            None => self.derive(&header)?,
+        };
+
+        key.decrypt(&header, data, length)
+    }
+
+    /// Derives the key of a header from the passphrases, keeping it for the next snapshots
+    /// encrypted under the same salt
+    fn derive(&mut self, header: &EnvelopeHeader) -> Result<EncryptionKey, Error> {
+        let missing = || Error::DecryptFailed.logged(format!("no key with id {}", header.key_id));
+        let kdf = header.kdf.ok_or_else(missing)?;
+
+        for passphrase in &self.passphrases {
+            let key = EncryptionKey::new(kdf.derive(passphrase)?, Some(kdf));
+            if key.id == header.key_id {
+                self.keys.push(key.clone());
+                return Ok(key);
+            }
+        }
+
+        Err(missing())
+    }
+}
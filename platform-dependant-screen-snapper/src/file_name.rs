@@ -0,0 +1,232 @@
+use std::{fmt::Display, str::FromStr};
+
+use chrono::{
+    DateTime, Local,
+    format::{Item, StrftimeItems},
+};
+
+pub const DEFAULT_FILE_NAME: &str = "{id}";
+pub const DEFAULT_TIME_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    /// Sequence number, zero-padded to the given width
+    Id(usize),
+    /// Capture time, in strftime format
+    Time(String),
+    Monitor,
+    Hostname,
+    Width,
+    Height,
+}
+
+impl Segment {
+    fn parse(placeholder: &str) -> Result<Self, String> {
+        let (name, argument) = match placeholder.split_once(':') {
+            Some((name, argument)) => (name, Some(argument)),
+            None => (placeholder, None),
+        };
+
+        match (name, argument) {
+            ("id", None) => Ok(Self::Id(0)),
+            ("id", Some(width)) => width
+                .parse()
+                .map(Self::Id)
+                .map_err(|_| format!("Invalid sequence number width: {width}")),
+            ("time", None) => Ok(Self::Time(DEFAULT_TIME_FORMAT.to_string())),
+            ("time", Some(format)) => {
+                if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+                    return Err(format!("Invalid time format: {format}"));
+                }
+
+                Ok(Self::Time(format.to_string()))
+            }
+            ("monitor", None) => Ok(Self::Monitor),
+            ("hostname", None) => Ok(Self::Hostname),
+            ("width", None) => Ok(Self::Width),
+            ("height", None) => Ok(Self::Height),
+            _ => Err(format!("Unknown file name placeholder: {{{placeholder}}}")),
+        }
+    }
+
+    /// Whether the rendered value is made of digits only
+    fn is_numeric(&self) -> bool {
+        matches!(self, Self::Id(_) | Self::Width | Self::Height)
+    }
+}
+
+/// Values the placeholders of a [`FileNameTemplate`] are replaced with
+pub struct FileNameContext<'a> {
+    pub id: usize,
+    pub time: DateTime<Local>,
+    pub monitor: Option<&'a str>,
+    pub hostname: &'a str,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// File name, without extension, made of literal text and placeholders:
+/// `{id}` or `{id:N}` (zero-padded to N digits), `{time}` or `{time:FORMAT}` (strftime-style),
+/// `{monitor}`, `{hostname}`, `{width}` and `{height}`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileNameTemplate {
+    segments: Vec<Segment>,
+}
+
+impl FileNameTemplate {
+    pub fn has_id(&self) -> bool {
+        self.segments
+            .iter()
+            .any(|segment| matches!(segment, Segment::Id(_)))
+    }
+
+    pub fn render(&self, context: &FileNameContext) -> String {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Literal(literal) => literal.clone(),
+                Segment::Id(width) => format!("{:0width$}", context.id),
+                Segment::Time(format) => sanitize(&context.time.format(format).to_string()),
+                Segment::Monitor => sanitize(context.monitor.unwrap_or("unknown")),
+                Segment::Hostname => sanitize(context.hostname),
+                Segment::Width => context.width.to_string(),
+                Segment::Height => context.height.to_string(),
+            })
+            .collect()
+    }
+
+    /// Sequence number of a file name (without extension) rendered from this template
+    pub fn parse_id(&self, file_name: &str) -> Option<usize> {
+        match_segments(&self.segments, file_name).flatten()
+    }
+}
+
+impl Default for FileNameTemplate {
+    fn default() -> Self {
+        Self {
+            segments: vec![Segment::Id(0)],
+        }
+    }
+}
+
+impl Display for FileNameTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(literal) => write!(f, "{literal}")?,
+                Segment::Id(0) => write!(f, "{{id}}")?,
+                Segment::Id(width) => write!(f, "{{id:{width}}}")?,
+                Segment::Time(format) => write!(f, "{{time:{format}}}")?,
+                Segment::Monitor => write!(f, "{{monitor}}")?,
+                Segment::Hostname => write!(f, "{{hostname}}")?,
+                Segment::Width => write!(f, "{{width}}")?,
+                Segment::Height => write!(f, "{{height}}")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for FileNameTemplate {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value.contains(['/', '\\']) {
+            return Err(format!(
+                "File name templates cannot contain path separators: {value}"
+            ));
+        }
+
+        let mut segments = Vec::new();
+        let mut rest = value;
+
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                segments.push(Segment::Literal(rest[..start].to_string()));
+            }
+
+            let end = rest[start..]
+                .find('}')
+                .ok_or_else(|| format!("Unclosed placeholder in file name: {value}"))?;
+            segments.push(Segment::parse(&rest[start + 1..start + end])?);
+            rest = &rest[start + end + 1..];
+        }
+
+        if !rest.is_empty() {
+            segments.push(Segment::Literal(rest.to_string()));
+        }
+
+        if segments.is_empty() {
+            return Err("Empty file name template".to_string());
+        }
+
+        Ok(Self { segments })
+    }
+}
+
+/// Matches a file name against the segments, returning the sequence number if any
+fn match_segments(segments: &[Segment], file_name: &str) -> Option<Option<usize>> {
+    let Some((segment, rest)) = segments.split_first() else {
+        return file_name.is_empty().then_some(None);
+    };
+
+    if let Segment::Literal(literal) = segment {
+        return match_segments(rest, file_name.strip_prefix(literal.as_str())?);
+    }
+
+    // Placeholders span at least one character, shortest match first
+    let ends = file_name
+        .char_indices()
+        .map(|(index, _)| index)
+        .skip(1)
+        .chain([file_name.len()]);
+
+    for end in ends {
+        let value = &file_name[..end];
+        if segment.is_numeric() && !value.bytes().all(|byte| byte.is_ascii_digit()) {
+            break;
+        }
+
+        let Some(id) = match_segments(rest, &file_name[end..]) else {
+            continue;
+        };
+
+        return match segment {
+            Segment::Id(_) => Some(value.parse().ok()),
+            _ => Some(id),
+        };
+    }
+
+    None
+}
+
+/// Keeps substituted values from introducing path separators
+fn sanitize(value: &str) -> String {
+    value.replace(['/', '\\'], "_")
+}
+
+/// What to do when a snapshot would be saved over an existing file
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CollisionPolicy {
+    /// Keep the existing file and drop the snapshot
+    Skip,
+    Overwrite,
+    /// Append "-2", "-3"... to the file name until it is free
+    #[default]
+    Suffix,
+}
+
+impl FromStr for CollisionPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "skip" => Ok(Self::Skip),
+            "overwrite" => Ok(Self::Overwrite),
+            "suffix" => Ok(Self::Suffix),
+            _ => Err(format!("Unknown collision policy: {value}")),
+        }
+    }
+}
@@ -0,0 +1,495 @@
+use std::{
+    collections::HashSet,
+    io::{Cursor, Seek, Write},
+    str::FromStr,
+    sync::{
+        Arc, Mutex, MutexGuard,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use async_trait::async_trait;
+use bon::Builder;
+use chrono::{DateTime, Local};
+use image::{
+    ExtendedColorType, ImageEncoder, ImageError, ImageResult,
+    codecs::{
+        bmp::BmpEncoder,
+        jpeg::JpegEncoder,
+        png::{CompressionType, FilterType, PngEncoder},
+        qoi::QoiEncoder,
+        tiff::TiffEncoder,
+        webp::WebPEncoder,
+    },
+};
+use remotia::{
+    buffers::BytesMut,
+    traits::{FrameError, FrameProcessor, FrameProperties, PullableFrameProperties},
+};
+
+use crate::{
+    catalog::{Catalog, CatalogEntry},
+    change_detector::ChangeScore,
+    encryption::{EncryptionKey, encrypted_extension},
+    error::Error,
+    file_name::{CollisionPolicy, FileNameContext, FileNameTemplate},
+    metadata::{CaptureSource, INDEX_FILE_NAME, SnapshotMetadata},
+    pixel_format::{FrameFormat, PixelFormat},
+    storage::{StorageSink, StoredObject},
+    worker_pool::WorkerPool,
+};
+
+pub const DEFAULT_JPEG_QUALITY: u8 = 90;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImageFormat {
+    Png {
+        compression: CompressionType,
+        filter: FilterType,
+    },
+    Jpeg {
+        quality: u8,
+    },
+    WebP,
+    Qoi,
+    Bmp,
+    Tiff,
+}
+
+impl Default for ImageFormat {
+    fn default() -> Self {
+        Self::Png {
+            compression: CompressionType::default(),
+            filter: FilterType::default(),
+        }
+    }
+}
+
+impl ImageFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Png { .. } => "png",
+            Self::Jpeg { .. } => "jpg",
+            Self::WebP => "webp",
+            Self::Qoi => "qoi",
+            Self::Bmp => "bmp",
+            Self::Tiff => "tiff",
+        }
+    }
+
+    pub fn encode<W: Write + Seek>(
+        &self,
+        mut writer: W,
+        pixels: &[u8],
+        width: u32,
+        height: u32,
+    ) -> ImageResult<()> {
+        let color_type = ExtendedColorType::Rgb8;
+
+        match *self {
+            Self::Png {
+                compression,
+                filter,
+            } => PngEncoder::new_with_quality(writer, compression, filter)
+                .write_image(pixels, width, height, color_type),
+            Self::Jpeg { quality } => JpegEncoder::new_with_quality(writer, quality)
+                .write_image(pixels, width, height, color_type),
+            Self::WebP => {
+                WebPEncoder::new_lossless(writer).write_image(pixels, width, height, color_type)
+            }
+            Self::Qoi => QoiEncoder::new(writer).write_image(pixels, width, height, color_type),
+            Self::Bmp => {
+                BmpEncoder::new(&mut writer).write_image(pixels, width, height, color_type)
+            }
+            Self::Tiff => TiffEncoder::new(writer).write_image(pixels, width, height, color_type),
+        }
+    }
+}
+
+impl FromStr for ImageFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "png" => Ok(Self::default()),
+            "jpg" | "jpeg" => Ok(Self::Jpeg {
+                quality: DEFAULT_JPEG_QUALITY,
+            }),
+            "webp" => Ok(Self::WebP),
+            "qoi" => Ok(Self::Qoi),
+            "bmp" => Ok(Self::Bmp),
+            "tif" | "tiff" => Ok(Self::Tiff),
+            _ => Err(format!("Unknown image format: {value}")),
+        }
+    }
+}
+
+#[derive(Builder)]
+pub struct ImageBufferSaver<K> {
+    #[builder(field = 0)]
+    current_id: usize,
+
+    buffer_key: K,
+
+    /// Where the images are stored, e.g. a directory
+    sink: Arc<dyn StorageSink>,
+
+    #[builder(default)]
+    file_name: FileNameTemplate,
+
+    #[builder(default)]
+    on_collision: CollisionPolicy,
+
+    /// Continue numbering from the highest sequence number found in the sink
+    #[builder(default)]
+    resume: bool,
+
+    /// Captured surface, for the `{monitor}` placeholder and the snapshot metadata
+    #[builder(default)]
+    source: CaptureSource,
+
+    /// Store the capture metadata in the image files, for the formats supporting it
+    #[builder(default = true)]
+    embed_metadata: bool,
+
+    /// Append the capture metadata of each snapshot to a JSON Lines index in the sink
+    #[builder(default)]
+    index: bool,
+
+    /// Encrypt the images with this key, their names ending with ".enc".
+    /// The index is kept in clear, listing their names and capture metadata.
+    encryption: Option<Arc<EncryptionKey>>,
+
+    /// Record each snapshot in this catalog once stored
+    catalog: Option<Arc<Catalog>>,
+
+    /// Encode and write the images on these workers, instead of within the pipeline.
+    /// Their errors are then logged and counted, the frames going on as saved.
+    workers: Option<WorkerPool>,
+
+    /// Keys handed to the workers but not stored yet
+    #[builder(skip)]
+    reserved: Arc<Mutex<HashSet<String>>>,
+
+    /// Saves which failed on the workers
+    #[builder(skip)]
+    failures: Arc<AtomicUsize>,
+
+    /// Size of the last frame saved, each frame being saved at the size of its own format
+    #[builder(skip)]
+    size: Option<(u32, u32)>,
+
+    #[builder(skip = gethostname::gethostname().to_string_lossy().into_owned())]
+    hostname: String,
+
+    #[builder(default)]
+    format: ImageFormat,
+}
+
+impl<K> ImageBufferSaver<K> {
+    fn extension(&self) -> String {
+        match self.encryption {
+            Some(_) => encrypted_extension(self.format.extension()),
+            None => self.format.extension().to_string(),
+        }
+    }
+
+    /// Highest sequence number among the objects whose keys match the template
+    fn last_id(&self, objects: &[StoredObject]) -> usize {
+        let suffix = format!(".{}", self.extension());
+
+        objects
+            .iter()
+            .filter_map(|object| self.file_name.parse_id(object.key.strip_suffix(&suffix)?))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Checks and converts the frame, copying its pixels so that the buffer can be released
+    fn prepare(
+        &self,
+        file_name: String,
+        time: DateTime<Local>,
+        format: FrameFormat,
+        change_score: Option<ChangeScore>,
+        buffer: &[u8],
+    ) -> Result<SaveJob, Error> {
+        if !format.fits(buffer) {
+            return Err(Error::SizeMismatch.logged(format!(
+                "{} bytes do not match {:?}",
+                buffer.len(),
+                format
+            )));
+        }
+
+        // Named once its key is picked
+        let metadata = SnapshotMetadata::new(
+            String::new(),
+            time,
+            &self.hostname,
+            &self.source,
+            format.pixel_format,
+            format.width,
+            format.height,
+        );
+
+        Ok(SaveJob {
+            pixels: format.convert(buffer, PixelFormat::Rgb8),
+            width: format.width,
+            height: format.height,
+            format: self.format,
+            metadata,
+            embed_metadata: self.embed_metadata,
+            index: self.index,
+            encryption: self.encryption.clone(),
+            catalog: self.catalog.clone(),
+            change_score: change_score.map(|ChangeScore(score)| score),
+            sink: self.sink.clone(),
+            file_name,
+            extension: self.extension(),
+            on_collision: self.on_collision,
+            reserved: self.reserved.clone(),
+        })
+    }
+}
+
+fn lock<T>(shared: &Mutex<T>) -> MutexGuard<'_, T> {
+    shared.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Releases a reserved key once its image is stored or dropped
+struct Reservation {
+    key: String,
+    reserved: Arc<Mutex<HashSet<String>>>,
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        lock(&self.reserved).remove(&self.key);
+    }
+}
+
+/// Encoding and writing of a snapshot, independent of the frame it was taken from.
+/// Jobs make all the calls to the sink, which may block, e.g. on uploads.
+struct SaveJob {
+    /// RGB pixels
+    pixels: Vec<u8>,
+    width: u32,
+    height: u32,
+    format: ImageFormat,
+    metadata: SnapshotMetadata,
+    embed_metadata: bool,
+    /// Append the metadata to the JSON Lines index
+    index: bool,
+    encryption: Option<Arc<EncryptionKey>>,
+    catalog: Option<Arc<Catalog>>,
+    change_score: Option<f64>,
+    sink: Arc<dyn StorageSink>,
+    /// Rendered file name template, without extension
+    file_name: String,
+    extension: String,
+    on_collision: CollisionPolicy,
+    reserved: Arc<Mutex<HashSet<String>>>,
+}
+
+impl SaveJob {
+    fn location(&self) -> String {
+        self.sink
+            .location(&format!("{}.{}", self.file_name, self.extension))
+    }
+
+    /// Picks the key of the snapshot and reserves it until stored, `None` if it should be skipped
+    fn reserve(&self) -> Result<Option<Reservation>, Error> {
+        let (file_name, extension) = (&self.file_name, &self.extension);
+        let key = format!("{file_name}.{extension}");
+
+        // Held while checking the sink, so that concurrent jobs do not pick the same key
+        let mut reserved = lock(&self.reserved);
+        let is_free = |key: &String| -> Result<bool, Error> {
+            Ok(!reserved.contains(key) && !self.sink.exists(key)?)
+        };
+
+        let key = match self.on_collision {
+            CollisionPolicy::Overwrite => Some(key),
+            CollisionPolicy::Skip => is_free(&key)?.then_some(key),
+            CollisionPolicy::Suffix => {
+                let suffixed = (2..).map(|suffix| format!("{file_name}-{suffix}.{extension}"));
+
+                [key]
+                    .into_iter()
+                    .chain(suffixed)
+                    .find_map(|key| is_free(&key).map(|free| free.then_some(key)).transpose())
+                    .transpose()?
+            }
+        };
+
+        Ok(key.map(|key| {
+            reserved.insert(key.clone());
+            Reservation {
+                key,
+                reserved: self.reserved.clone(),
+            }
+        }))
+    }
+
+    fn run(mut self) -> Result<(), Error> {
+        let Some(reservation) = self.reserve()? else {
+            log::info!("{} already exists, skipping", self.location());
+            return Ok(());
+        };
+        let key = reservation.key.as_str();
+        let location = self.sink.location(key);
+
+        log::info!("Saving screenshot to {}...", location);
+        self.metadata.file = key.to_string();
+
+        let mut encoded = Cursor::new(Vec::new());
+        self.format
+            .encode(&mut encoded, &self.pixels, self.width, self.height)
+            .map_err(|err| match err {
+                ImageError::IoError(err) => Error::WriteFailed.logged(err),
+                err => Error::EncodeFailed.logged(err),
+            })?;
+        let mut encoded = encoded.into_inner();
+
+        if self.embed_metadata {
+            self.metadata.embed(&self.format, &mut encoded);
+        }
+
+        if let Some(key) = &self.encryption {
+            encoded = key.encrypt(&encoded)?;
+        }
+
+        self.sink.put(key, &encoded)?;
+
+        if self.index {
+            append_to_index(self.sink.as_ref(), &self.metadata)?;
+        }
+
+        if let Some(catalog) = &self.catalog {
+            catalog.record(&CatalogEntry::new(
+                location,
+                &self.metadata,
+                &encoded,
+                self.change_score,
+            ))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn append_to_index(sink: &dyn StorageSink, metadata: &SnapshotMetadata) -> Result<(), Error> {
+    let mut line = serde_json::to_string(metadata).map_err(|err| Error::WriteFailed.logged(err))?;
+    line.push('\n');
+
+    sink.append(INDEX_FILE_NAME, line.as_bytes())
+}
+
+impl<K: Send + Copy> ImageBufferSaver<K> {
+    /// Saves the snapshot of a frame, leaving its buffer in place
+    async fn save<F>(&mut self, frame_data: &mut F) -> Result<(), Error>
+    where
+        F: Send
+            + PullableFrameProperties<K, BytesMut>
+            + FrameProperties<K, FrameFormat>
+            + FrameProperties<K, ChangeScore>,
+    {
+        let time = Local::now();
+
+        let Some(format) = FrameProperties::<K, FrameFormat>::get(frame_data, &self.buffer_key)
+        else {
+            return Err(Error::MissingFormat);
+        };
+
+        let size = (format.width, format.height);
+        if self.size.is_some_and(|last| last != size) {
+            log::info!("Saving {}x{} snapshots from now on", size.0, size.1);
+        }
+        self.size = Some(size);
+
+        // Not saved until numbering can resume, rather than restarting from 1
+        if self.resume && self.current_id == 0 {
+            let sink = self.sink.clone();
+            let objects = tokio::task::spawn_blocking(move || sink.list())
+                .await
+                .unwrap_or_else(|err| Err(Error::StorageUnavailable.logged(err)))?;
+
+            self.current_id = self.last_id(&objects);
+            if self.current_id > 0 {
+                log::info!("Resuming numbering after snapshot {}", self.current_id);
+            }
+        }
+
+        self.current_id += 1;
+
+        let context = FileNameContext {
+            id: self.current_id,
+            time,
+            monitor: self
+                .source
+                .monitor
+                .as_ref()
+                .map(|monitor| monitor.name.as_str()),
+            hostname: &self.hostname,
+            width: format.width,
+            height: format.height,
+        };
+
+        let file_name = self.file_name.render(&context);
+
+        let Some(buffer) = frame_data.pull(&self.buffer_key) else {
+            return Err(Error::MissingBuffer);
+        };
+
+        let change_score = FrameProperties::<K, ChangeScore>::get(frame_data, &self.buffer_key);
+        let job = self.prepare(file_name, time, format, change_score, &buffer);
+        frame_data.push(self.buffer_key, buffer);
+
+        match (job, &self.workers) {
+            (Ok(job), Some(workers)) => {
+                let failures = self.failures.clone();
+                workers
+                    .submit(job.location(), move || {
+                        let location = job.location();
+                        if let Err(error) = job.run() {
+                            let failed = failures.fetch_add(1, Ordering::Relaxed) + 1;
+                            log::error!(
+                                "Unable to save {}: {:?} ({} failed saves so far)",
+                                location,
+                                error,
+                                failed
+                            );
+                        }
+                    })
+                    .await;
+
+                Ok(())
+            }
+            (Ok(job), None) => tokio::task::spawn_blocking(move || job.run())
+                .await
+                .unwrap_or_else(|err| Err(Error::WriteFailed.logged(err))),
+            (Err(error), _) => Err(error),
+        }
+    }
+}
+
+#[async_trait]
+impl<K, F> FrameProcessor<F> for ImageBufferSaver<K>
+where
+    F: Send + 'static,
+    K: Send + Copy,
+    F: PullableFrameProperties<K, BytesMut>
+        + FrameProperties<K, FrameFormat>
+        + FrameProperties<K, ChangeScore>
+        + FrameError<Error>,
+{
+    async fn process(&mut self, mut frame_data: F) -> Option<F> {
+        // The errors of the workers belong to earlier frames, which went on as saved
+        if let Err(error) = self.save(&mut frame_data).await {
+            frame_data.report_error(error);
+        }
+
+        Some(frame_data)
+    }
+}
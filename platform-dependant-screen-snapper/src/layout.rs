@@ -0,0 +1,137 @@
+use image::{RgbaImage, imageops};
+use serde::Serialize;
+
+use crate::{
+    error::Error,
+    pixel_format::{FrameFormat, PixelFormat},
+};
+
+/// Position and size of a monitor in the desktop coordinate space
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct MonitorGeometry {
+    pub name: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl MonitorGeometry {
+    /// Format of the frames captured from this monitor alone
+    pub fn frame_format(&self) -> FrameFormat {
+        FrameFormat::packed(PixelFormat::Rgb8, self.width, self.height)
+    }
+
+    pub fn buffer_size(&self) -> usize {
+        self.frame_format().buffer_size()
+    }
+}
+
+/// Arrangement of the captured monitors, in capture order
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DisplayLayout {
+    pub monitors: Vec<MonitorGeometry>,
+}
+
+impl DisplayLayout {
+    /// Top-left corner of the box enclosing all monitors
+    pub fn origin(&self) -> (i32, i32) {
+        (
+            self.monitors
+                .iter()
+                .map(|monitor| monitor.x)
+                .min()
+                .unwrap_or(0),
+            self.monitors
+                .iter()
+                .map(|monitor| monitor.y)
+                .min()
+                .unwrap_or(0),
+        )
+    }
+
+    /// Size of the box enclosing all monitors
+    pub fn canvas_size(&self) -> (u32, u32) {
+        let (origin_x, origin_y) = self.origin();
+        let right = self
+            .monitors
+            .iter()
+            .map(|monitor| monitor.x as i64 + monitor.width as i64)
+            .max()
+            .unwrap_or(0);
+        let bottom = self
+            .monitors
+            .iter()
+            .map(|monitor| monitor.y as i64 + monitor.height as i64)
+            .max()
+            .unwrap_or(0);
+
+        (
+            (bottom - origin_y as i64) as u32,
+            (right - origin_x as i64) as u32,
+        )
+    }
+
+    /// Format of the stitched frames
+    pub fn canvas_format(&self) -> FrameFormat {
+        let (height, width) = self.canvas_size();
+        FrameFormat::packed(PixelFormat::Rgb8, width, height)
+    }
+
+    pub fn canvas_buffer_size(&self) -> usize {
+        self.canvas_format().buffer_size()
+    }
+
+    /// Checks a captured image against the geometry of the monitor it comes from
+    pub fn check(&self, index: usize, image: &RgbaImage) -> Result<(), Error> {
+        let monitor = &self.monitors[index];
+        if image.dimensions() != (monitor.width, monitor.height) {
+            return Err(Error::SizeMismatch.logged(format!(
+                "monitor {} captured at {}x{}, expected {}x{}",
+                monitor.name,
+                image.width(),
+                image.height(),
+                monitor.width,
+                monitor.height
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Places the monitor images, in layout order, onto a single canvas
+    pub fn stitch(&self, images: &[RgbaImage]) -> Result<RgbaImage, Error> {
+        if images.len() != self.monitors.len() {
+            return Err(Error::SizeMismatch.logged(format!(
+                "{} images for {} monitors",
+                images.len(),
+                self.monitors.len()
+            )));
+        }
+
+        let (origin_x, origin_y) = self.origin();
+        let (height, width) = self.canvas_size();
+        let mut canvas = RgbaImage::new(width, height);
+
+        for (index, (monitor, image)) in self.monitors.iter().zip(images).enumerate() {
+            self.check(index, image)?;
+            imageops::replace(
+                &mut canvas,
+                image,
+                monitor.x as i64 - origin_x as i64,
+                monitor.y as i64 - origin_y as i64,
+            );
+        }
+
+        Ok(canvas)
+    }
+}
+
+/// Where multi-monitor captures are written
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MultiMonitorMode<K> {
+    /// Each monitor goes to its own buffer, the N-th key receiving the N-th monitor
+    PerMonitor(Vec<K>),
+    /// All monitors are stitched into a single buffer, following their positions
+    Stitched(K),
+}
@@ -1,13 +1,41 @@
 #[cfg(not(any(feature = "xcap", feature = "wayshot")))]
 compile_error!("No snapper backened enabled");
 
-#[cfg(all(feature = "xcap", feature = "wayshot"))]
-compile_error!("Compiling with both wayshot and xcap support is not currently supported.");
-
-pub mod png_saver;
+pub mod catalog;
+pub mod change_detector;
+pub mod control;
+pub mod encryption;
+pub mod error;
+pub mod file_name;
+pub mod geometry;
+pub mod image_saver;
+pub mod layout;
+pub mod metadata;
+pub mod monitor_selector;
+pub mod overlay;
+pub mod pattern_capturer;
+pub mod pixel_format;
+pub mod redaction;
+pub mod region;
+pub mod resize;
+pub mod retention;
+pub mod s3_storage;
+pub mod screen_capturer;
+pub mod storage;
+pub mod timelapse;
+pub mod worker_pool;
 
 #[cfg(feature = "wayshot")]
 pub mod wayshot_capturer;
 
+#[cfg(feature = "wayshot")]
+pub mod wayshot_multi_capturer;
+
 #[cfg(feature = "xcap")]
 pub mod xcap_capturer;
+
+#[cfg(feature = "xcap")]
+pub mod xcap_multi_capturer;
+
+#[cfg(feature = "xcap")]
+pub mod xcap_window_capturer;
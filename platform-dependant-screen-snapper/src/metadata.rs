@@ -0,0 +1,261 @@
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+use crate::{image_saver::ImageFormat, layout::MonitorGeometry, pixel_format::PixelFormat};
+
+pub const SOFTWARE: &str = concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION"));
+pub const INDEX_FILE_NAME: &str = "index.jsonl";
+
+/// What a saver knows about the surface its frames are captured from
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CaptureSource {
+    /// Capture backend, e.g. "xcap" or "wayshot"
+    pub backend: Option<String>,
+    pub monitor: Option<MonitorGeometry>,
+}
+
+/// Capture metadata of a saved snapshot, one line of the JSON Lines index
+#[derive(Clone, Debug, Serialize)]
+pub struct SnapshotMetadata {
+    /// File name, relative to the output directory
+    pub file: String,
+    pub time: DateTime<Local>,
+    pub host: String,
+    pub backend: Option<String>,
+    pub monitor: Option<MonitorGeometry>,
+    /// Pixel format of the captured buffer
+    pub pixel_format: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl SnapshotMetadata {
+    pub fn new(
+        file: String,
+        time: DateTime<Local>,
+        host: &str,
+        source: &CaptureSource,
+        pixel_format: PixelFormat,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        Self {
+            file,
+            time,
+            host: host.to_string(),
+            backend: source.backend.clone(),
+            monitor: source.monitor.clone(),
+            pixel_format: pixel_format.to_string(),
+            width,
+            height,
+        }
+    }
+
+    /// One-line summary, e.g. "DP-1 1920x1080+0+0 via xcap, captured as bgra8"
+    pub fn description(&self) -> String {
+        let monitor = match &self.monitor {
+            Some(monitor) => format!(
+                "{} {}x{}{:+}{:+}",
+                monitor.name, monitor.width, monitor.height, monitor.x, monitor.y
+            ),
+            None => format!("{}x{}", self.width, self.height),
+        };
+
+        match &self.backend {
+            Some(backend) => format!("{monitor} via {backend}, captured as {}", self.pixel_format),
+            None => format!("{monitor}, captured as {}", self.pixel_format),
+        }
+    }
+
+    /// Key-value pairs stored in the text chunks of PNG files
+    fn text_fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![
+            ("Creation Time", self.time.to_rfc3339()),
+            ("Software", SOFTWARE.to_string()),
+            ("Description", self.description()),
+            ("Host", self.host.clone()),
+            ("Pixel Format", self.pixel_format.clone()),
+        ];
+
+        if let Some(backend) = &self.backend {
+            fields.push(("Backend", backend.clone()));
+        }
+
+        if let Some(monitor) = &self.monitor {
+            fields.push(("Monitor", monitor.name.clone()));
+            fields.push((
+                "Monitor Geometry",
+                format!(
+                    "{}x{}{:+}{:+}",
+                    monitor.width, monitor.height, monitor.x, monitor.y
+                ),
+            ));
+        }
+
+        fields
+    }
+
+    /// Adds the metadata to an encoded image, for the formats supporting it
+    pub fn embed(&self, format: &ImageFormat, encoded: &mut Vec<u8>) {
+        match format {
+            ImageFormat::Png { .. } => png_chunks::insert(encoded, &self.text_fields()),
+            ImageFormat::Jpeg { .. } => {
+                let exif = exif::tiff(&[
+                    (exif::IMAGE_DESCRIPTION, self.description()),
+                    (exif::SOFTWARE, SOFTWARE.to_string()),
+                    (
+                        exif::DATE_TIME,
+                        self.time.format("%Y:%m:%d %H:%M:%S").to_string(),
+                    ),
+                    (exif::HOST_COMPUTER, self.host.clone()),
+                ]);
+                exif::insert(encoded, &exif);
+            }
+            _ => log::debug!("No embedded metadata support for {}", format.extension()),
+        }
+    }
+}
+
+pub(crate) mod png_chunks {
+    const SIGNATURE_SIZE: usize = 8;
+
+    /// Inserts text chunks before the IEND chunk, using iTXt for non-Latin-1 text
+    pub(super) fn insert(png: &mut Vec<u8>, fields: &[(&str, String)]) {
+        let Some(end) = iend_offset(png) else {
+            log::warn!("Malformed PNG, skipping metadata");
+            return;
+        };
+
+        let mut chunks = Vec::new();
+        for (keyword, text) in fields {
+            if text.chars().all(|c| (c as u32) < 0x100 && c != '\0') {
+                let data: Vec<u8> = keyword
+                    .bytes()
+                    .chain([0])
+                    .chain(text.chars().map(|c| c as u8))
+                    .collect();
+                write_chunk(&mut chunks, b"tEXt", &data);
+            } else {
+                // Uncompressed, no language tag nor translated keyword
+                let mut data = keyword.as_bytes().to_vec();
+                data.extend_from_slice(&[0, 0, 0, 0, 0]);
+                data.extend_from_slice(text.as_bytes());
+                write_chunk(&mut chunks, b"iTXt", &data);
+            }
+        }
+
+        png.splice(end..end, chunks);
+    }
+
+    fn iend_offset(png: &[u8]) -> Option<usize> {
+        let mut offset = SIGNATURE_SIZE;
+        while offset + 8 <= png.len() {
+            let length = u32::from_be_bytes(png[offset..offset + 4].try_into().ok()?) as usize;
+            if &png[offset + 4..offset + 8] == b"IEND" {
+                return Some(offset);
+            }
+
+            // Length, type, data and CRC
+            offset += 12 + length;
+        }
+
+        None
+    }
+
+    pub(crate) fn write_chunk(output: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(chunk_type);
+        hasher.update(data);
+
+        output.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        output.extend_from_slice(chunk_type);
+        output.extend_from_slice(data);
+        output.extend_from_slice(&hasher.finalize().to_be_bytes());
+    }
+}
+
+mod exif {
+    pub(super) const IMAGE_DESCRIPTION: u16 = 0x010e;
+    pub(super) const SOFTWARE: u16 = 0x0131;
+    pub(super) const DATE_TIME: u16 = 0x0132;
+    pub(super) const HOST_COMPUTER: u16 = 0x013c;
+
+    const ASCII: u16 = 2;
+    const HEADER_SIZE: usize = 8;
+    const ENTRY_SIZE: usize = 12;
+
+    /// Big-endian TIFF structure holding a single IFD of ASCII fields
+    pub(super) fn tiff(fields: &[(u16, String)]) -> Vec<u8> {
+        let mut fields = fields.to_vec();
+        fields.sort_by_key(|(tag, _)| *tag);
+
+        let ifd_size = 2 + fields.len() * ENTRY_SIZE + 4;
+        let mut entries = Vec::with_capacity(ifd_size);
+        let mut values = Vec::new();
+
+        entries.extend_from_slice(&(fields.len() as u16).to_be_bytes());
+        for (tag, text) in &fields {
+            // NUL-terminated, non-ASCII characters replaced
+            let mut value: Vec<u8> = text
+                .chars()
+                .map(|c| {
+                    if c.is_ascii() && c != '\0' {
+                        c as u8
+                    } else {
+                        b'?'
+                    }
+                })
+                .collect();
+            value.push(0);
+
+            entries.extend_from_slice(&tag.to_be_bytes());
+            entries.extend_from_slice(&ASCII.to_be_bytes());
+            entries.extend_from_slice(&(value.len() as u32).to_be_bytes());
+
+            if value.len() <= 4 {
+                value.resize(4, 0);
+                entries.extend_from_slice(&value);
+            } else {
+                let offset = HEADER_SIZE + ifd_size + values.len();
+                entries.extend_from_slice(&(offset as u32).to_be_bytes());
+                values.extend_from_slice(&value);
+            }
+        }
+        // No next IFD
+        entries.extend_from_slice(&[0; 4]);
+
+        let mut tiff = b"MM\0\x2a".to_vec();
+        tiff.extend_from_slice(&(HEADER_SIZE as u32).to_be_bytes());
+        tiff.extend_from_slice(&entries);
+        tiff.extend_from_slice(&values);
+        tiff
+    }
+
+    /// Inserts an APP1 Exif segment after the SOI marker and the JFIF APP0 segment, if any
+    pub(super) fn insert(jpeg: &mut Vec<u8>, tiff: &[u8]) {
+        if !jpeg.starts_with(&[0xff, 0xd8]) {
+            log::warn!("Malformed JPEG, skipping metadata");
+            return;
+        }
+
+        let segment_size = 2 + 6 + tiff.len();
+        if segment_size > u16::MAX as usize {
+            log::warn!("EXIF metadata too large, skipping it");
+            return;
+        }
+
+        let mut offset = 2;
+        if jpeg.get(2..4) == Some(&[0xff, 0xe0])
+            && let Some(length) = jpeg.get(4..6)
+        {
+            offset += 2 + u16::from_be_bytes([length[0], length[1]]) as usize;
+        }
+
+        let mut segment = vec![0xff, 0xe1];
+        segment.extend_from_slice(&(segment_size as u16).to_be_bytes());
+        segment.extend_from_slice(b"Exif\0\0");
+        segment.extend_from_slice(tiff);
+
+        jpeg.splice(offset..offset, segment);
+    }
+}
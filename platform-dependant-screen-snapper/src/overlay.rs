@@ -0,0 +1,634 @@
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use bon::Builder;
+use chrono::{
+    Local,
+    format::{Item, StrftimeItems},
+};
+use remotia::{
+    buffers::BytesMut,
+    traits::{BorrowMutFrameProperties, FrameError, FrameProcessor, FrameProperties},
+};
+
+use crate::{error::Error, pixel_format::FrameFormat};
+
+pub const DEFAULT_OVERLAY_TEXT: &str = "{time} {hostname}";
+pub const DEFAULT_OVERLAY_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+pub const DEFAULT_OVERLAY_SCALE: u32 = 2;
+
+// Layout of the text, in font pixels
+const GLYPH_WIDTH: u32 = 5;
+const GLYPH_HEIGHT: u32 = 7;
+const CELL_WIDTH: u32 = GLYPH_WIDTH + 1;
+const LINE_HEIGHT: u32 = GLYPH_HEIGHT + 2;
+/// Between the text and the edges of its background box
+const PADDING: u32 = 2;
+/// Between the box and the edges of the frame
+const MARGIN: u32 = 4;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    /// Wall-clock time, in strftime format
+    Time(String),
+    Hostname,
+    Monitor,
+}
+
+impl Segment {
+    fn parse(placeholder: &str) -> Result<Self, String> {
+        match placeholder.split_once(':') {
+            None if placeholder == "time" => {
+                Ok(Self::Time(DEFAULT_OVERLAY_TIME_FORMAT.to_string()))
+            }
+            Some(("time", format)) => {
+                if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+                    return Err(format!("Invalid time format: {format}"));
+                }
+
+                Ok(Self::Time(format.to_string()))
+            }
+            None if placeholder == "hostname" => Ok(Self::Hostname),
+            None if placeholder == "monitor" => Ok(Self::Monitor),
+            _ => Err(format!("Unknown overlay placeholder: {{{placeholder}}}")),
+        }
+    }
+}
+
+/// Text made of literal text and placeholders: `{time}` or `{time:FORMAT}` (strftime-style),
+/// `{hostname}` and `{monitor}`. Lines are separated by newlines, or `\n` when parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OverlayText {
+    segments: Vec<Segment>,
+}
+
+impl OverlayText {
+    pub fn render(&self, hostname: &str, monitor: Option<&str>) -> String {
+        let now = Local::now();
+
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Literal(literal) => literal.clone(),
+                Segment::Time(format) => now.format(format).to_string(),
+                Segment::Hostname => hostname.to_string(),
+                Segment::Monitor => monitor.unwrap_or("unknown").to_string(),
+            })
+            .collect()
+    }
+}
+
+impl Default for OverlayText {
+    fn default() -> Self {
+        DEFAULT_OVERLAY_TEXT
+            .parse()
+            .expect("The default overlay text is valid")
+    }
+}
+
+impl FromStr for OverlayText {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        let mut rest = value;
+
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                segments.push(Segment::Literal(rest[..start].replace("\\n", "\n")));
+            }
+
+            let end = rest[start..]
+                .find('}')
+                .ok_or_else(|| format!("Unclosed placeholder in overlay text: {value}"))?;
+            segments.push(Segment::parse(&rest[start + 1..start + end])?);
+            rest = &rest[start + end + 1..];
+        }
+
+        if !rest.is_empty() {
+            segments.push(Segment::Literal(rest.replace("\\n", "\n")));
+        }
+
+        Ok(Self { segments })
+    }
+}
+
+/// Corner of the frame the text is drawn in, or top-left corner of its box
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OverlayPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+    At(u32, u32),
+}
+
+/// Parses "top-left", "top-right", "bottom-left", "bottom-right" or "<x>,<y>"
+impl FromStr for OverlayPosition {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "top-left" => Ok(Self::TopLeft),
+            "top-right" => Ok(Self::TopRight),
+            "bottom-left" => Ok(Self::BottomLeft),
+            "bottom-right" => Ok(Self::BottomRight),
+            _ => {
+                let invalid = || format!("Invalid overlay position: {value}");
+                let (x, y) = value.split_once(',').ok_or_else(invalid)?;
+                Ok(Self::At(
+                    x.trim().parse().map_err(|_| invalid())?,
+                    y.trim().parse().map_err(|_| invalid())?,
+                ))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Color(pub [u8; 3]);
+
+impl Color {
+    pub const BLACK: Self = Self([0, 0, 0]);
+    pub const WHITE: Self = Self([255, 255, 255]);
+}
+
+/// Parses a few color names, "#RRGGBB" or "RRGGBB"
+impl FromStr for Color {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "black" => return Ok(Self::BLACK),
+            "white" => return Ok(Self::WHITE),
+            "red" => return Ok(Self([255, 0, 0])),
+            "green" => return Ok(Self([0, 255, 0])),
+            "blue" => return Ok(Self([0, 0, 255])),
+            "yellow" => return Ok(Self([255, 255, 0])),
+            _ => {}
+        }
+
+        let hex = value.strip_prefix('#').unwrap_or(value);
+        if hex.len() != 6 || !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return Err(format!("Invalid color: {value}"));
+        }
+
+        let channel = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16);
+        Ok(Self([
+            channel(0).map_err(|err| err.to_string())?,
+            channel(2).map_err(|err| err.to_string())?,
+            channel(4).map_err(|err| err.to_string())?,
+        ]))
+    }
+}
+
+/// Burns a line or more of text, e.g. a timestamp, into a buffer in place.
+/// The font is embedded, and the text is clipped to the frame.
+#[derive(Builder)]
+pub struct TextOverlay<K> {
+    buffer_key: K,
+
+    #[builder(default)]
+    text: OverlayText,
+
+    /// Captured monitor, for the `{monitor}` placeholder
+    #[builder(into)]
+    monitor: Option<String>,
+
+    #[builder(skip = gethostname::gethostname().to_string_lossy().into_owned())]
+    hostname: String,
+
+    #[builder(default)]
+    position: OverlayPosition,
+
+    #[builder(default = Color::WHITE)]
+    color: Color,
+
+    /// Color of the box behind the text, none if unset
+    background: Option<Color>,
+
+    /// Size of a font pixel, in frame pixels
+    #[builder(default = DEFAULT_OVERLAY_SCALE)]
+    scale: u32,
+}
+
+impl<K> TextOverlay<K> {
+    fn draw(&self, format: FrameFormat, buffer: &mut [u8], text: &str) {
+        let lines: Vec<&str> = text.lines().collect();
+        let columns = lines
+            .iter()
+            .map(|line| line.chars().count() as u32)
+            .max()
+            .unwrap_or(0);
+
+        if columns == 0 {
+            return;
+        }
+
+        let scale = self.scale.max(1);
+        let box_width = (columns * CELL_WIDTH - 1 + 2 * PADDING) * scale;
+        let box_height = (lines.len() as u32 * LINE_HEIGHT - 2 + 2 * PADDING) * scale;
+        let margin = MARGIN * scale;
+
+        let right = format.width.saturating_sub(box_width + margin);
+        let bottom = format.height.saturating_sub(box_height + margin);
+        let (x, y) = match self.position {
+            OverlayPosition::TopLeft => (margin, margin),
+            OverlayPosition::TopRight => (right, margin),
+            OverlayPosition::BottomLeft => (margin, bottom),
+            OverlayPosition::BottomRight => (right, bottom),
+            OverlayPosition::At(x, y) => (x, y),
+        };
+
+        if let Some(background) = self.background {
+            fill(format, buffer, (x, y), (box_width, box_height), background);
+        }
+
+        for (line_index, line) in lines.iter().enumerate() {
+            let glyph_y = y as u64 + ((PADDING + line_index as u32 * LINE_HEIGHT) * scale) as u64;
+
+            for (column, character) in line.chars().enumerate() {
+                let glyph_x = x as u64 + ((PADDING + column as u32 * CELL_WIDTH) * scale) as u64;
+
+                for (row, bits) in glyph(character).iter().enumerate() {
+                    for bit in 0..GLYPH_WIDTH {
+                        if bits & (0b10000 >> bit) == 0 {
+                            continue;
+                        }
+
+                        let pixel_x = glyph_x + (bit * scale) as u64;
+                        let pixel_y = glyph_y + (row as u32 * scale) as u64;
+                        if pixel_x < format.width as u64 && pixel_y < format.height as u64 {
+                            fill(
+                                format,
+                                buffer,
+                                (pixel_x as u32, pixel_y as u32),
+                                (scale, scale),
+                                self.color,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Paints a rectangle, clipped to the frame
+fn fill(
+    format: FrameFormat,
+    buffer: &mut [u8],
+    (x, y): (u32, u32),
+    (width, height): (u32, u32),
+    Color(color): Color,
+) {
+    let right = (x as u64 + width as u64).min(format.width as u64) as usize;
+    let bottom = (y as u64 + height as u64).min(format.height as u64) as usize;
+    let bpp = format.pixel_format.bytes_per_pixel();
+
+    for row in y as usize..bottom {
+        for column in x as usize..right {
+            let offset = row * format.stride + column * bpp;
+            format
+                .pixel_format
+                .write(&mut buffer[offset..offset + bpp], color);
+        }
+    }
+}
+
+/// Glyph of a printable ASCII character, '?' standing for the others
+fn glyph(character: char) -> &'static [u8; 7] {
+    let index = (character as u32)
+        .checked_sub(' ' as u32)
+        .filter(|index| (*index as usize) < GLYPHS.len())
+        .unwrap_or('?' as u32 - ' ' as u32);
+
+    &GLYPHS[index as usize]
+}
+
+#[async_trait]
+impl<K, F> FrameProcessor<F> for TextOverlay<K>
+where
+    F: Send + 'static,
+    K: Send + Copy,
+    F: BorrowMutFrameProperties<K, BytesMut> + FrameProperties<K, FrameFormat> + FrameError<Error>,
+{
+    async fn process(&mut self, mut dto: F) -> Option<F> {
+        let Some(format) = dto.get(&self.buffer_key) else {
+            dto.report_error(Error::MissingFormat);
+            return Some(dto);
+        };
+
+        let text = self.text.render(&self.hostname, self.monitor.as_deref());
+
+        let Some(buffer) = dto.get_mut_ref(&self.buffer_key) else {
+            dto.report_error(Error::MissingBuffer);
+            return Some(dto);
+        };
+
+        if !format.fits(buffer) {
+            log::warn!(
+                "Buffer of {} bytes does not match {:?}, unable to draw the overlay",
+                buffer.len(),
+                format
+            );
+            dto.report_error(Error::SizeMismatch);
+            return Some(dto);
+        }
+
+        self.draw(format, buffer, &text);
+
+        Some(dto)
+    }
+}
+
+// 5x7 glyphs of the printable ASCII characters, from space to '~', one bit per column
+const GLYPHS: [[u8; 7]; 95] = [
+    [
+        0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000,
+    ], // space
+    [
+        0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00100,
+    ], // !
+    [
+        0b01010, 0b01010, 0b01010, 0b00000, 0b00000, 0b00000, 0b00000,
+    ], // "
+    [
+        0b01010, 0b01010, 0b11111, 0b01010, 0b11111, 0b01010, 0b01010,
+    ], // #
+    [
+        0b00100, 0b01111, 0b10100, 0b01110, 0b00101, 0b11110, 0b00100,
+    ], // $
+    [
+        0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011,
+    ], // %
+    [
+        0b01100, 0b10010, 0b10100, 0b01000, 0b10101, 0b10010, 0b01101,
+    ], // &
+    [
+        0b01100, 0b00100, 0b01000, 0b00000, 0b00000, 0b00000, 0b00000,
+    ], // '
+    [
+        0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010,
+    ], // (
+    [
+        0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000,
+    ], // )
+    [
+        0b00000, 0b00100, 0b10101, 0b01110, 0b10101, 0b00100, 0b00000,
+    ], // *
+    [
+        0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000,
+    ], // +
+    [
+        0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000,
+    ], // ,
+    [
+        0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000,
+    ], // -
+    [
+        0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100,
+    ], // .
+    [
+        0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000,
+    ], // /
+    [
+        0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110,
+    ], // 0
+    [
+        0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
+    ], // 1
+    [
+        0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111,
+    ], // 2
+    [
+        0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110,
+    ], // 3
+    [
+        0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010,
+    ], // 4
+    [
+        0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110,
+    ], // 5
+    [
+        0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110,
+    ], // 6
+    [
+        0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000,
+    ], // 7
+    [
+        0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110,
+    ], // 8
+    [
+        0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100,
+    ], // 9
+    [
+        0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000,
+    ], // :
+    [
+        0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b00100, 0b01000,
+    ], // ;
+    [
+        0b00010, 0b00100, 0b01000, 0b10000, 0b01000, 0b00100, 0b00010,
+    ], // <
+    [
+        0b00000, 0b00000, 0b11111, 0b00000, 0b11111, 0b00000, 0b00000,
+    ], // =
+    [
+        0b01000, 0b00100, 0b00010, 0b00001, 0b00010, 0b00100, 0b01000,
+    ], // >
+    [
+        0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100,
+    ], // ?
+    [
+        0b01110, 0b10001, 0b00001, 0b01101, 0b10101, 0b10101, 0b01110,
+    ], // @
+    [
+        0b01110, 0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001,
+    ], // A
+    [
+        0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110,
+    ], // B
+    [
+        0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110,
+    ], // C
+    [
+        0b11100, 0b10010, 0b10001, 0b10001, 0b10001, 0b10010, 0b11100,
+    ], // D
+    [
+        0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111,
+    ], // E
+    [
+        0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000,
+    ], // F
+    [
+        0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111,
+    ], // G
+    [
+        0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001,
+    ], // H
+    [
+        0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
+    ], // I
+    [
+        0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100,
+    ], // J
+    [
+        0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001,
+    ], // K
+    [
+        0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111,
+    ], // L
+    [
+        0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001,
+    ], // M
+    [
+        0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001,
+    ], // N
+    [
+        0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110,
+    ], // O
+    [
+        0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000,
+    ], // P
+    [
+        0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101,
+    ], // Q
+    [
+        0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001,
+    ], // R
+    [
+        0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110,
+    ], // S
+    [
+        0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100,
+    ], // T
+    [
+        0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110,
+    ], // U
+    [
+        0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100,
+    ], // V
+    [
+        0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010,
+    ], // W
+    [
+        0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001,
+    ], // X
+    [
+        0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100,
+    ], // Y
+    [
+        0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111,
+    ], // Z
+    [
+        0b01110, 0b01000, 0b01000, 0b01000, 0b01000, 0b01000, 0b01110,
+    ], // [
+    [
+        0b00000, 0b10000, 0b01000, 0b00100, 0b00010, 0b00001, 0b00000,
+    ], // \
+    [
+        0b01110, 0b00010, 0b00010, 0b00010, 0b00010, 0b00010, 0b01110,
+    ], // ]
+    [
+        0b00100, 0b01010, 0b10001, 0b00000, 0b00000, 0b00000, 0b00000,
+    ], // ^
+    [
+        0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b11111,
+    ], // _
+    [
+        0b01000, 0b00100, 0b00010, 0b00000, 0b00000, 0b00000, 0b00000,
+    ], // `
+    [
+        0b00000, 0b00000, 0b01110, 0b00001, 0b01111, 0b10001, 0b01111,
+    ], // a
+    [
+        0b10000, 0b10000, 0b10110, 0b11001, 0b10001, 0b10001, 0b11110,
+    ], // b
+    [
+        0b00000, 0b00000, 0b01110, 0b10000, 0b10000, 0b10001, 0b01110,
+    ], // c
+    [
+        0b00001, 0b00001, 0b01101, 0b10011, 0b10001, 0b10001, 0b01111,
+    ], // d
+    [
+        0b00000, 0b00000, 0b01110, 0b10001, 0b11111, 0b10000, 0b01110,
+    ], // e
+    [
+        0b00110, 0b01001, 0b01000, 0b11100, 0b01000, 0b01000, 0b01000,
+    ], // f
+    [
+        0b00000, 0b01111, 0b10001, 0b10001, 0b01111, 0b00001, 0b01110,
+    ], // g
+    [
+        0b10000, 0b10000, 0b10110, 0b11001, 0b10001, 0b10001, 0b10001,
+    ], // h
+    [
+        0b00100, 0b00000, 0b01100, 0b00100, 0b00100, 0b00100, 0b01110,
+    ], // i
+    [
+        0b00010, 0b00000, 0b00110, 0b00010, 0b00010, 0b10010, 0b01100,
+    ], // j
+    [
+        0b10000, 0b10000, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010,
+    ], // k
+    [
+        0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
+    ], // l
+    [
+        0b00000, 0b00000, 0b11010, 0b10101, 0b10101, 0b10001, 0b10001,
+    ], // m
+    [
+        0b00000, 0b00000, 0b10110, 0b11001, 0b10001, 0b10001, 0b10001,
+    ], // n
+    [
+        0b00000, 0b00000, 0b01110, 0b10001, 0b10001, 0b10001, 0b01110,
+    ], // o
+    [
+        0b00000, 0b00000, 0b11110, 0b10001, 0b11110, 0b10000, 0b10000,
+    ], // p
+    [
+        0b00000, 0b00000, 0b01101, 0b10011, 0b01111, 0b00001, 0b00001,
+    ], // q
+    [
+        0b00000, 0b00000, 0b10110, 0b11001, 0b10000, 0b10000, 0b10000,
+    ], // r
+    [
+        0b00000, 0b00000, 0b01110, 0b10000, 0b01110, 0b00001, 0b11110,
+    ], // s
+    [
+        0b01000, 0b01000, 0b11100, 0b01000, 0b01000, 0b01001, 0b00110,
+    ], // t
+    [
+        0b00000, 0b00000, 0b10001, 0b10001, 0b10001, 0b10011, 0b01101,
+    ], // u
+    [
+        0b00000, 0b00000, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100,
+    ], // v
+    [
+        0b00000, 0b00000, 0b10001, 0b10001, 0b10101, 0b10101, 0b01010,
+    ], // w
+    [
+        0b00000, 0b00000, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001,
+    ], // x
+    [
+        0b00000, 0b00000, 0b10001, 0b10001, 0b01111, 0b00001, 0b01110,
+    ], // y
+    [
+        0b00000, 0b00000, 0b11111, 0b00010, 0b00100, 0b01000, 0b11111,
+    ], // z
+    [
+        0b00010, 0b00100, 0b00100, 0b01000, 0b00100, 0b00100, 0b00010,
+    ], // {
+    [
+        0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100,
+    ], // |
+    [
+        0b01000, 0b00100, 0b00100, 0b00010, 0b00100, 0b00100, 0b01000,
+    ], // }
+    [
+        0b00000, 0b00000, 0b01000, 0b10101, 0b00010, 0b00000, 0b00000,
+    ], // ~
+];
@@ -0,0 +1,75 @@
+use std::str::FromStr;
+
+use image::{RgbaImage, imageops};
+
+use crate::{
+    error::Error,
+    pixel_format::{FrameFormat, PixelFormat},
+};
+
+/// Capture rectangle, relative to the top-left corner of the captured surface
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Region {
+    /// Size of the RGB buffer holding a frame cropped to this region
+    pub fn buffer_size(&self) -> usize {
+        FrameFormat::packed(PixelFormat::Rgb8, self.width, self.height).buffer_size()
+    }
+
+    pub fn fits(&self, height: u32, width: u32) -> bool {
+        self.x as u64 + self.width as u64 <= width as u64
+            && self.y as u64 + self.height as u64 <= height as u64
+    }
+
+    pub fn crop(&self, image: &RgbaImage) -> Result<RgbaImage, Error> {
+        if !self.fits(image.height(), image.width()) {
+            return Err(Error::RegionOutOfBounds.logged(format!(
+                "{} does not fit in a {}x{} frame",
+                self,
+                image.width(),
+                image.height()
+            )));
+        }
+
+        Ok(imageops::crop_imm(image, self.x, self.y, self.width, self.height).to_image())
+    }
+}
+
+impl std::fmt::Display for Region {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}+{}+{}", self.width, self.height, self.x, self.y)
+    }
+}
+
+/// Parses X11-style geometries, e.g. "640x480+100+50"
+impl FromStr for Region {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid region (expected WIDTHxHEIGHT+X+Y): {value}");
+
+        let (size, offset) = value.split_once('+').ok_or_else(invalid)?;
+        let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+        let (x, y) = offset.split_once('+').ok_or_else(invalid)?;
+
+        let parse = |field: &str| field.parse::<u32>().map_err(|_| invalid());
+        let region = Self {
+            x: parse(x)?,
+            y: parse(y)?,
+            width: parse(width)?,
+            height: parse(height)?,
+        };
+
+        if region.width == 0 || region.height == 0 {
+            return Err(format!("Empty region: {value}"));
+        }
+
+        Ok(region)
+    }
+}
@@ -0,0 +1,265 @@
+use std::{fmt::Display, str::FromStr};
+
+use async_trait::async_trait;
+use bon::Builder;
+use image::{
+    RgbImage,
+    imageops::{self, FilterType},
+};
+use remotia::{
+    buffers::{BufMut, BytesMut},
+    traits::{FrameError, FrameProcessor, FrameProperties, PullableFrameProperties},
+};
+
+use crate::{
+    error::Error,
+    geometry::GeometryChange,
+    pixel_format::{FrameFormat, PixelFormat},
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResizeFilter {
+    Nearest,
+    #[default]
+    Bilinear,
+    Lanczos,
+}
+
+impl ResizeFilter {
+    fn filter_type(&self) -> FilterType {
+        match self {
+            Self::Nearest => FilterType::Nearest,
+            Self::Bilinear => FilterType::Triangle,
+            Self::Lanczos => FilterType::Lanczos3,
+        }
+    }
+}
+
+impl FromStr for ResizeFilter {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "nearest" => Ok(Self::Nearest),
+            "bilinear" => Ok(Self::Bilinear),
+            "lanczos" => Ok(Self::Lanczos),
+            _ => Err(format!("Unknown resize filter: {value}")),
+        }
+    }
+}
+
+/// How a frame is made to match the target size
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResizeMode {
+    /// Scale the frame to fit within the target size, keeping its aspect ratio.
+    /// One side of the output may then be shorter than the target.
+    #[default]
+    Fit,
+    /// Scale the frame to cover the target size, keeping its aspect ratio,
+    /// and crop what overflows on either side
+    Fill,
+    /// Stretch the frame to the target size
+    Exact,
+}
+
+impl FromStr for ResizeMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "fit" => Ok(Self::Fit),
+            "fill" => Ok(Self::Fill),
+            "exact" => Ok(Self::Exact),
+            _ => Err(format!("Unknown resize mode: {value}")),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Size {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Display for Size {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+/// Parses "WIDTHxHEIGHT", e.g. "320x180"
+impl FromStr for Size {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid size (expected WIDTHxHEIGHT): {value}");
+
+        let (width, height) = value.split_once('x').ok_or_else(invalid)?;
+        let size = Self {
+            width: width.parse().map_err(|_| invalid())?,
+            height: height.parse().map_err(|_| invalid())?,
+        };
+
+        if size.width == 0 || size.height == 0 {
+            return Err(format!("Empty size: {value}"));
+        }
+
+        Ok(size)
+    }
+}
+
+/// Writes a scaled copy of a buffer into another one, in the same pixel format without padding.
+/// The output buffer is expected to be borrowed beforehand.
+#[derive(Builder)]
+pub struct Resizer<K> {
+    input_key: K,
+    output_key: K,
+
+    size: Size,
+
+    #[builder(default)]
+    mode: ResizeMode,
+
+    #[builder(default)]
+    filter: ResizeFilter,
+}
+
+impl<K> Resizer<K> {
+    /// Size of the output frames for input frames of the given size, as (height, width)
+    pub fn output_size(&self, height: u32, width: u32) -> (u32, u32) {
+        let Size {
+            width: target_width,
+            height: target_height,
+        } = self.size;
+
+        match self.mode {
+            ResizeMode::Fit => {
+                let scale = f64::min(
+                    target_width as f64 / width.max(1) as f64,
+                    target_height as f64 / height.max(1) as f64,
+                );
+                let scaled = |side: u32| ((side as f64 * scale).round() as u32).max(1);
+
+                (
+                    scaled(height).min(target_height),
+                    scaled(width).min(target_width),
+                )
+            }
+            ResizeMode::Fill | ResizeMode::Exact => (target_height, target_width),
+        }
+    }
+
+    /// Format of the output frames for input frames of the given format
+    pub fn output_format(&self, format: FrameFormat) -> FrameFormat {
+        let (height, width) = self.output_size(format.height, format.width);
+        FrameFormat::packed(format.pixel_format, width, height)
+    }
+
+    fn resize(&self, format: FrameFormat, buffer: &[u8]) -> Result<(FrameFormat, Vec<u8>), Error> {
+        if !format.fits(buffer) {
+            return Err(Error::SizeMismatch.logged(format!(
+                "{} bytes do not match {:?}",
+                buffer.len(),
+                format
+            )));
+        }
+
+        let output_format = self.output_format(format);
+        let pixels = format.convert(buffer, PixelFormat::Rgb8);
+        let image =
+            RgbImage::from_raw(format.width, format.height, pixels).ok_or(Error::SizeMismatch)?;
+
+        // Cropped to the aspect ratio of the output first, so that only what is kept gets scaled
+        let image = match self.mode {
+            ResizeMode::Fill => {
+                let (width, height) = (format.width as u64, format.height as u64);
+                let (target_width, target_height) =
+                    (output_format.width as u64, output_format.height as u64);
+
+                let crop_width = width.min(height * target_width / target_height).max(1) as u32;
+                let crop_height = height.min(width * target_height / target_width).max(1) as u32;
+
+                imageops::crop_imm(
+                    &image,
+                    (format.width - crop_width) / 2,
+                    (format.height - crop_height) / 2,
+                    crop_width,
+                    crop_height,
+                )
+                .to_image()
+            }
+            ResizeMode::Fit | ResizeMode::Exact => image,
+        };
+
+        let resized = imageops::resize(
+            &image,
+            output_format.width,
+            output_format.height,
+            self.filter.filter_type(),
+        );
+
+        let pixels = FrameFormat::packed(PixelFormat::Rgb8, resized.width(), resized.height())
+            .convert(resized.as_raw(), output_format.pixel_format);
+
+        Ok((output_format, pixels))
+    }
+}
+
+#[async_trait]
+impl<K, F> FrameProcessor<F> for Resizer<K>
+where
+    F: Send + 'static,
+    K: Send + Copy,
+    F: PullableFrameProperties<K, BytesMut>
+        + FrameProperties<K, FrameFormat>
+        + FrameProperties<K, GeometryChange>
+        + FrameError<Error>,
+{
+    async fn process(&mut self, mut frame_data: F) -> Option<F> {
+        let Some(format) = FrameProperties::<K, FrameFormat>::get(&frame_data, &self.input_key)
+        else {
+            frame_data.report_error(Error::MissingFormat);
+            return Some(frame_data);
+        };
+
+        let Some(input) = frame_data.pull(&self.input_key) else {
+            frame_data.report_error(Error::MissingBuffer);
+            return Some(frame_data);
+        };
+
+        let resized = self.resize(format, &input);
+        frame_data.push(self.input_key, input);
+
+        let (output_format, pixels) = match resized {
+            Ok(resized) => resized,
+            Err(error) => {
+                frame_data.report_error(error);
+                return Some(frame_data);
+            }
+        };
+
+        let Some(mut output) = frame_data.pull(&self.output_key) else {
+            frame_data.report_error(Error::MissingBuffer);
+            return Some(frame_data);
+        };
+
+        output.clear();
+        output.put_slice(&pixels);
+
+        frame_data.push(self.output_key, output);
+        frame_data.set(self.output_key, output_format);
+
+        // The output only changes size along with the input in the fit mode
+        let change = FrameProperties::<K, GeometryChange>::get(&frame_data, &self.input_key)
+            .map(|change| GeometryChange {
+                previous: self.output_format(change.previous),
+                current: output_format,
+            })
+            .filter(|change| change.previous != change.current);
+        if let Some(change) = change {
+            frame_data.set(self.output_key, change);
+        }
+
+        Some(frame_data)
+    }
+}
@@ -0,0 +1,494 @@
+use std::{
+    io::Read,
+    sync::atomic::{AtomicU64, Ordering},
+    thread,
+    time::Duration,
+};
+
+use bon::Builder;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    error::Error,
+    storage::{ReadPosition, StorageSink, StoredObject, complete_lines},
+};
+
+pub const DEFAULT_S3_REGION: &str = "us-east-1";
+pub const DEFAULT_UPLOAD_ATTEMPTS: u32 = 4;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Key and contents of a part appended to an object
+type Part = (String, Vec<u8>);
+
+/// How failed requests are retried, waiting twice as long after each attempt
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts at most, the first one included
+    pub attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: DEFAULT_UPLOAD_ATTEMPTS,
+            initial_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(8),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait after the given failed attempt, counting from 1
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+            .min(self.max_backoff)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct S3Credentials {
+    pub access_key: String,
+    pub secret_key: String,
+}
+
+/// Stores the objects in a bucket of an S3-compatible service, such as MinIO or a local stand-in.
+/// Buckets are addressed by path, and requests are signed with AWS Signature Version 4.
+/// Requests failing on the network or on the server side are retried with backoff,
+/// and reported once out of attempts. Requests block, sinks being called by the saving jobs.
+#[derive(Builder)]
+pub struct S3Sink {
+    /// Base URL of the service, e.g. "http://127.0.0.1:9000"
+    #[builder(into)]
+    endpoint: String,
+
+    #[builder(into)]
+    bucket: String,
+
+    /// Prepended to the keys, e.g. "snapshots/"
+    #[builder(into, default)]
+    prefix: String,
+
+    #[builder(into, default = DEFAULT_S3_REGION.to_string())]
+    region: String,
+
+    /// Requests are sent anonymously without credentials
+    credentials: Option<S3Credentials>,
+
+    #[builder(default)]
+    retry: RetryPolicy,
+
+    #[builder(skip = ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build())]
+    agent: ureq::Agent,
+
+    /// Parts appended so far, telling apart those appended at the same time
+    #[builder(skip)]
+    appended: AtomicU64,
+}
+
+impl S3Sink {
+    fn object_path(&self, key: &str) -> String {
+        format!("/{}/{}{}", self.bucket, self.prefix, key)
+    }
+
+    /// Prefix of the parts appended to an object, relative to the sink prefix
+    pub fn parts_prefix(key: &str) -> String {
+        format!("{key}.parts/")
+    }
+
+    /// Host the requests are sent to, as signed
+    fn host(&self) -> &str {
+        let authority = self
+            .endpoint
+            .split_once("://")
+            .map_or(self.endpoint.as_str(), |(_, rest)| rest);
+
+        authority.split('/').next().unwrap_or(authority)
+    }
+
+    /// Sends a request, retrying it on transient failures. Missing objects are returned as `None`.
+    fn request(
+        &self,
+        method: &str,
+        path: &str,
+        query: &[(&str, &str)],
+        body: &[u8],
+        error: Error,
+    ) -> Result<Option<ureq::Response>, Error> {
+        let mut attempt = 1;
+
+        loop {
+            let err = match self.send(method, path, query, body).map_err(|err| *err) {
+                Ok(response) => return Ok(Some(response)),
+                Err(ureq::Error::Status(404, _)) => return Ok(None),
+                Err(err) => err,
+            };
+
+            let transient = match &err {
+                ureq::Error::Status(status, _) => *status == 429 || *status >= 500,
+                ureq::Error::Transport(_) => true,
+            };
+
+            if !transient || attempt >= self.retry.attempts {
+                return Err(error.logged(format!(
+                    "{method} {path} failed after {attempt} attempts: {err}"
+                )));
+            }
+
+            let backoff = self.retry.backoff(attempt);
+            log::warn!("{method} {path} failed ({err}), retrying in {backoff:?}");
+            thread::sleep(backoff);
+            attempt += 1;
+        }
+    }
+
+    fn send(
+        &self,
+        method: &str,
+        path: &str,
+        query: &[(&str, &str)],
+        body: &[u8],
+    ) -> Result<ureq::Response, Box<ureq::Error>> {
+        let canonical_uri = uri_encode(path, false);
+
+        let mut query: Vec<(String, String)> = query
+            .iter()
+            .map(|(name, value)| (uri_encode(name, true), uri_encode(value, true)))
+            .collect();
+        query.sort();
+        let canonical_query = query
+            .iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let mut url = format!("{}{}", self.endpoint.trim_end_matches('/'), canonical_uri);
+        if !canonical_query.is_empty() {
+            url = format!("{url}?{canonical_query}");
+        }
+
+        let mut request = self.agent.request(method, &url);
+        if let Some(credentials) = &self.credentials {
+            let headers = self.signed_headers(
+                credentials,
+                Utc::now(),
+                method,
+                &canonical_uri,
+                &canonical_query,
+                body,
+            );
+            for (name, value) in headers {
+                request = request.set(name, &value);
+            }
+        }
+
+        match method {
+            "PUT" => request.send_bytes(body),
+            _ => request.call(),
+        }
+        .map_err(Box::new)
+    }
+
+    /// Headers authenticating a request, following AWS Signature Version 4
+    fn signed_headers(
+        &self,
+        credentials: &S3Credentials,
+        time: DateTime<Utc>,
+        method: &str,
+        canonical_uri: &str,
+        canonical_query: &str,
+        body: &[u8],
+    ) -> [(&'static str, String); 3] {
+        let amz_date = time.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = time.format("%Y%m%d").to_string();
+        let payload_hash = hex::encode(Sha256::digest(body));
+
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_headers = format!(
+            "host:{}\nx-amz-content-sha256:{payload_hash}\nx-amz-date:{amz_date}\n",
+            self.host()
+        );
+        let canonical_request = format!(
+            "{method}\n{canonical_uri}\n{canonical_query}\n{canonical_headers}\n{signed_headers}\n{payload_hash}"
+        );
+
+        let scope = format!("{date}/{}/s3/aws4_request", self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+            hex::encode(Sha256::digest(canonical_request))
+        );
+
+        let signing_key = [date.as_str(), &self.region, "s3", "aws4_request"]
+            .into_iter()
+            .fold(
+                format!("AWS4{}", credentials.secret_key).into_bytes(),
+                |key, part| hmac_sha256(&key, part.as_bytes()),
+            );
+        let signature = hex::encode(hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+
+        [
+            ("x-amz-date", amz_date),
+            ("x-amz-content-sha256", payload_hash),
+            (
+                "authorization",
+                format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+                    credentials.access_key
+                ),
+            ),
+        ]
+    }
+
+    /// Objects whose keys start with the given prefix, relative to the sink prefix, in key
+    /// order and following the pages of the listing. Nested keys are left out when listing
+    /// by directory.
+    fn list_under(&self, prefix: &str, by_directory: bool) -> Result<Vec<StoredObject>, Error> {
+        let bucket_path = format!("/{}", self.bucket);
+        let full_prefix = format!("{}{}", self.prefix, prefix);
+        let mut objects = Vec::new();
+        let mut continuation_token: Option<String> = None;
+
+        loop {
+            let mut query = vec![("list-type", "2"), ("prefix", full_prefix.as_str())];
+            if by_directory {
+                query.push(("delimiter", "/"));
+            }
+            if let Some(token) = &continuation_token {
+                query.push(("continuation-token", token.as_str()));
+            }
+
+            let response = self
+                .request("GET", &bucket_path, &query, &[], Error::StorageUnavailable)?
+                .ok_or_else(|| {
+                    Error::StorageUnavailable.logged(format!("no bucket named {}", self.bucket))
+                })?;
+            let listing = response
+                .into_string()
+                .map_err(|err| Error::StorageUnavailable.logged(err))?;
+
+            objects.extend(
+                xml_values(&listing, "Contents")
+                    .iter()
+                    .filter_map(|contents| {
+                        let key = xml_values(contents, "Key").pop()?;
+                        let size = xml_values(contents, "Size").pop()?.parse().ok()?;
+
+                        Some(StoredObject {
+                            key: key.strip_prefix(self.prefix.as_str())?.to_string(),
+                            size,
+                        })
+                    }),
+            );
+
+            continuation_token = xml_values(&listing, "NextContinuationToken").pop();
+            if continuation_token.is_none() {
+                return Ok(objects);
+            }
+        }
+    }
+
+    /// Downloads an object and the parts appended to it, with their keys, in key order.
+    /// The parts are listed first: those merged into the object in the meantime are then
+    /// found in it, and missing by the time they are downloaded.
+    fn download(&self, key: &str) -> Result<(Option<Vec<u8>>, Vec<Part>), Error> {
+        let listed = self.list_under(&Self::parts_prefix(key), false)?;
+        let object = self.get(key)?;
+
+        let mut parts = Vec::with_capacity(listed.len());
+        for part in listed {
+            if let Some(data) = self.get(&part.key)? {
+                parts.push((part.key, data));
+            }
+        }
+
+        Ok((object, parts))
+    }
+
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, Error> {
+        let Some(response) = self.request(
+            "GET",
+            &self.object_path(key),
+            &[],
+            &[],
+            Error::StorageUnavailable,
+        )?
+        else {
+            return Ok(None);
+        };
+
+        let mut data = Vec::new();
+        response
+            .into_reader()
+            .read_to_end(&mut data)
+            .map_err(|err| Error::StorageUnavailable.logged(err))?;
+
+        Ok(Some(data))
+    }
+}
+
+impl StorageSink for S3Sink {
+    fn put(&self, key: &str, data: &[u8]) -> Result<(), Error> {
+        self.request(
+            "PUT",
+            &self.object_path(key),
+            &[],
+            data,
+            Error::UploadFailed,
+        )?
+        .ok_or_else(|| Error::UploadFailed.logged(format!("no bucket named {}", self.bucket)))?;
+
+        Ok(())
+    }
+
+    /// Uploads the data as a new part of the object, so that appending costs a single
+    /// upload of the data whatever the size of the object. The parts are stored under
+    /// "{key}.parts/", named after their upload time, until `read_lines` merges them.
+    fn append(&self, key: &str, data: &[u8]) -> Result<(), Error> {
+        let part = format!(
+            "{}{}-{:06}",
+            Self::parts_prefix(key),
+            Utc::now().format("%Y%m%dT%H%M%S%.9fZ"),
+            self.appended.fetch_add(1, Ordering::Relaxed) % 1_000_000
+        );
+
+        self.put(&part, data)
+    }
+
+    /// Downloads the object followed by the parts not merged into it yet
+    fn read(&self, key: &str) -> Result<Option<Vec<u8>>, Error> {
+        let (object, parts) = self.download(key)?;
+        if object.is_none() && parts.is_empty() {
+            return Ok(None);
+        }
+
+        let mut object = object.unwrap_or_default();
+        for (_, data) in parts {
+            object.extend(data);
+        }
+
+        Ok(Some(object))
+    }
+
+    /// Returns the lines of the object from the position on, followed by those of all its
+    /// parts, which are then merged into the object and deleted so that they do not pile up
+    /// in the bucket. As a part is only merged by the reader returning its lines, none is
+    /// skipped, whatever its name and whenever it was uploaded.
+    fn read_lines(
+        &self,
+        key: &str,
+        from: &ReadPosition,
+    ) -> Result<(Vec<String>, ReadPosition), Error> {
+        let (object, parts) = self.download(key)?;
+        let mut object = object.unwrap_or_default();
+
+        // The object only ever holds complete lines, as it is written by merging parts
+        let unread = object.get(from.offset as usize..).unwrap_or_default();
+        let (mut lines, _) = complete_lines(unread, from);
+        if parts.is_empty() {
+            return Ok((
+                lines,
+                ReadPosition {
+                    offset: object.len() as u64,
+                },
+            ));
+        }
+
+        for (_, data) in &parts {
+            lines.extend(String::from_utf8_lossy(data).lines().map(str::to_string));
+            object.extend(data);
+        }
+
+        self.put(key, &object)?;
+        for (part, _) in &parts {
+            self.delete(part)?;
+        }
+        log::debug!("Merged {} parts into {}", parts.len(), self.location(key));
+
+        Ok((
+            lines,
+            ReadPosition {
+                offset: object.len() as u64,
+            },
+        ))
+    }
+
+    fn exists(&self, key: &str) -> Result<bool, Error> {
+        Ok(self
+            .request(
+                "HEAD",
+                &self.object_path(key),
+                &[],
+                &[],
+                Error::StorageUnavailable,
+            )?
+            .is_some())
+    }
+
+    fn delete(&self, key: &str) -> Result<(), Error> {
+        self.request(
+            "DELETE",
+            &self.object_path(key),
+            &[],
+            &[],
+            Error::WriteFailed,
+        )?;
+
+        Ok(())
+    }
+
+    /// Objects right under the prefix
+    fn list(&self) -> Result<Vec<StoredObject>, Error> {
+        self.list_under("", true)
+    }
+
+    fn location(&self, key: &str) -> String {
+        format!("s3://{}/{}{}", self.bucket, self.prefix, key)
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent-encodes everything but the unreserved characters, and the slashes of paths
+fn uri_encode(value: &str, encode_slash: bool) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            b'/' if !encode_slash => "/".to_string(),
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+/// Unescaped contents of the elements with the given name
+fn xml_values(xml: &str, name: &str) -> Vec<String> {
+    let open = format!("<{name}>");
+    let close = format!("</{name}>");
+
+    xml.split(open.as_str())
+        .skip(1)
+        .filter_map(|rest| {
+            let (value, _) = rest.split_once(close.as_str())?;
+
+            Some(
+                value
+                    .replace("&lt;", "<")
+                    .replace("&gt;", ">")
+                    .replace("&quot;", "\"")
+                    .replace("&apos;", "'")
+                    .replace("&amp;", "&"),
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,253 @@
+use std::{env, fmt::Display, str::FromStr};
+
+use async_trait::async_trait;
+use bon::bon;
+use remotia::{
+    buffers::BytesMut,
+    traits::{FrameError, FrameProcessor, FrameProperties, PullableFrameProperties},
+};
+
+use crate::{
+    error::Error, geometry::GeometryChange, metadata::CaptureSource,
+    monitor_selector::MonitorSelector, pixel_format::FrameFormat, region::Region,
+};
+
+#[cfg(feature = "wayshot")]
+use crate::wayshot_capturer::{WayshotCapturer, wayshot_utils};
+
+#[cfg(feature = "xcap")]
+use crate::xcap_capturer::{XCapCapturer, xcap_utils};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    XCap,
+    Wayshot,
+}
+
+impl Backend {
+    /// Picks the backend matching the current graphical session, if any
+    pub fn detect() -> Option<Self> {
+        let is_set = |variable| env::var_os(variable).is_some_and(|value| !value.is_empty());
+
+        if is_set("WAYLAND_DISPLAY") {
+            Some(Self::Wayshot)
+        } else if is_set("DISPLAY") {
+            Some(Self::XCap)
+        } else {
+            None
+        }
+    }
+
+    pub fn fallback(&self) -> Self {
+        match self {
+            Self::XCap => Self::Wayshot,
+            Self::Wayshot => Self::XCap,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        match self {
+            Self::XCap => cfg!(feature = "xcap"),
+            Self::Wayshot => cfg!(feature = "wayshot"),
+        }
+    }
+}
+
+impl Display for Backend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::XCap => write!(f, "xcap"),
+            Self::Wayshot => write!(f, "wayshot"),
+        }
+    }
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "xcap" => Ok(Self::XCap),
+            "wayshot" => Ok(Self::Wayshot),
+            _ => Err(format!("Unknown capture backend: {value}")),
+        }
+    }
+}
+
+/// Capturer wrapping whichever compiled-in backend could be initialised
+pub enum ScreenCapturer<K> {
+    #[cfg(feature = "xcap")]
+    XCap(XCapCapturer<K>),
+
+    #[cfg(feature = "wayshot")]
+    Wayshot(WayshotCapturer<K>),
+}
+
+#[bon]
+impl<K: Copy> ScreenCapturer<K> {
+    /// Tries the requested (or detected) backend first, then falls back to the other one.
+    /// The monitor selector only applies to XCap, Wayshot blends every output.
+    #[builder]
+    pub fn new(
+        buffer_key: K,
+        backend: Option<Backend>,
+        #[builder(default)] monitor: MonitorSelector,
+        region: Option<Region>,
+    ) -> Result<Self, Error> {
+        let preferred = backend.or_else(Backend::detect).unwrap_or(Backend::XCap);
+
+        let mut last_error = Error::BackendUnavailable;
+        for candidate in [preferred, preferred.fallback()] {
+            if !candidate.is_enabled() {
+                log::debug!("{:?} backend not compiled in, skipping", candidate);
+                continue;
+            }
+
+            match Self::open(candidate, buffer_key, &monitor, region) {
+                Ok(capturer) => {
+                    log::info!("Capturing with the {:?} backend", candidate);
+                    return Ok(capturer);
+                }
+                Err(error) => {
+                    log::warn!(
+                        "Unable to initialise the {:?} backend: {:?}",
+                        candidate,
+                        error
+                    );
+                    last_error = error;
+                }
+            }
+        }
+
+        Err(last_error)
+    }
+
+    #[cfg_attr(not(feature = "xcap"), allow(unused_variables))]
+    fn open(
+        backend: Backend,
+        buffer_key: K,
+        monitor: &MonitorSelector,
+        region: Option<Region>,
+    ) -> Result<Self, Error> {
+        match backend {
+            #[cfg(feature = "xcap")]
+            Backend::XCap => {
+                let mut capturer = XCapCapturer::builder()
+                    .buffer_key(buffer_key)
+                    .monitor(monitor.clone())
+                    .maybe_region(region)
+                    .build();
+                capturer.open()?;
+                Ok(Self::XCap(capturer))
+            }
+
+            #[cfg(feature = "wayshot")]
+            Backend::Wayshot => {
+                let mut capturer = WayshotCapturer::builder()
+                    .buffer_key(buffer_key)
+                    .maybe_region(region)
+                    .build();
+                capturer.open()?;
+                Ok(Self::Wayshot(capturer))
+            }
+
+            #[allow(unreachable_patterns)]
+            _ => Err(Error::BackendUnavailable),
+        }
+    }
+}
+
+impl<K> ScreenCapturer<K> {
+    pub fn backend(&self) -> Backend {
+        match self {
+            #[cfg(feature = "xcap")]
+            Self::XCap(_) => Backend::XCap,
+
+            #[cfg(feature = "wayshot")]
+            Self::Wayshot(_) => Backend::Wayshot,
+        }
+    }
+
+    pub fn display_size(&self) -> Result<(u32, u32), Error> {
+        match self {
+            #[cfg(feature = "xcap")]
+            Self::XCap(capturer) => xcap_utils::display_size(capturer.monitor()),
+
+            #[cfg(feature = "wayshot")]
+            Self::Wayshot(_) => wayshot_utils::display_size(),
+        }
+    }
+
+    /// Backend and monitor the frames come from, Wayshot blending every output
+    pub fn capture_source(&self) -> CaptureSource {
+        let monitor = match self {
+            #[cfg(feature = "xcap")]
+            Self::XCap(capturer) => capturer.monitor_geometry(),
+
+            #[cfg(feature = "wayshot")]
+            Self::Wayshot(_) => None,
+        };
+
+        CaptureSource {
+            backend: Some(self.backend().to_string()),
+            monitor,
+        }
+    }
+
+    /// Top-left corner of the captured frames in desktop coordinates, unknown with Wayshot
+    pub fn capture_origin(&self) -> Option<(i32, i32)> {
+        match self {
+            #[cfg(feature = "xcap")]
+            Self::XCap(capturer) => capturer.capture_origin(),
+
+            #[cfg(feature = "wayshot")]
+            Self::Wayshot(_) => None,
+        }
+    }
+
+    /// Size of the captured frames, accounting for the capture region
+    pub fn capture_size(&self) -> Result<(u32, u32), Error> {
+        match self {
+            #[cfg(feature = "xcap")]
+            Self::XCap(capturer) => capturer.capture_size(),
+
+            #[cfg(feature = "wayshot")]
+            Self::Wayshot(capturer) => capturer.capture_size(),
+        }
+    }
+
+    pub fn frame_format(&self) -> Result<FrameFormat, Error> {
+        match self {
+            #[cfg(feature = "xcap")]
+            Self::XCap(capturer) => capturer.frame_format(),
+
+            #[cfg(feature = "wayshot")]
+            Self::Wayshot(capturer) => capturer.frame_format(),
+        }
+    }
+
+    pub fn buffer_size(&self) -> Result<usize, Error> {
+        Ok(self.frame_format()?.buffer_size())
+    }
+}
+
+#[async_trait]
+impl<K, F> FrameProcessor<F> for ScreenCapturer<K>
+where
+    F: Send + 'static,
+    K: Send + Copy,
+    F: PullableFrameProperties<K, BytesMut>
+        + FrameProperties<K, FrameFormat>
+        + FrameProperties<K, GeometryChange>
+        + FrameError<Error>,
+{
+    async fn process(&mut self, dto: F) -> Option<F> {
+        match self {
+            #[cfg(feature = "xcap")]
+            Self::XCap(capturer) => capturer.process(dto).await,
+
+            #[cfg(feature = "wayshot")]
+            Self::Wayshot(capturer) => capturer.process(dto).await,
+        }
+    }
+}
@@ -1,27 +1,165 @@
 use async_trait::async_trait;
 use bon::Builder;
-use libwayshot::WayshotConnection;
+use image::DynamicImage;
+use libwayshot::{WayshotConnection, output::OutputInfo};
 use remotia::{
     buffers::{BufMut, BytesMut},
-    traits::{FrameProcessor, PullableFrameProperties},
+    traits::{FrameError, FrameProcessor, FrameProperties, PullableFrameProperties},
+};
+
+use crate::{
+    error::Error,
+    geometry::{GeometryChange, GeometryTracker},
+    layout::{DisplayLayout, MonitorGeometry},
+    pixel_format::{FrameFormat, PixelFormat},
+    region::Region,
 };
 
 #[derive(Builder)]
 pub struct WayshotCapturer<K> {
+    #[builder(skip)]
+    connection: Option<WayshotConnection>,
+
+    #[builder(skip)]
+    tracker: GeometryTracker,
+
     buffer_key: K,
+    region: Option<Region>,
+}
+
+impl<K> WayshotCapturer<K> {
+    pub fn region(&self) -> Option<Region> {
+        self.region
+    }
+
+    /// Size of the captured frames, either the region's or the whole display's
+    pub fn capture_size(&self) -> Result<(u32, u32), Error> {
+        match self.region {
+            Some(region) => Ok((region.height, region.width)),
+            None => wayshot_utils::display_size(),
+        }
+    }
+
+    /// Format of the captured frames, which are packed RGB
+    pub fn frame_format(&self) -> Result<FrameFormat, Error> {
+        let (height, width) = self.capture_size()?;
+        Ok(FrameFormat::packed(PixelFormat::Rgb8, width, height))
+    }
+
+    pub fn buffer_size(&self) -> Result<usize, Error> {
+        Ok(self.frame_format()?.buffer_size())
+    }
+
+    /// Opens the Wayland connection ahead of the first capture, checking that the region fits in the display.
+    /// A first frame of another format than the one opened is declared as a geometry change.
+    pub fn open(&mut self) -> Result<(), Error> {
+        if self.connection.is_none() {
+            self.connection = Some(wayshot_utils::connect()?);
+        }
+
+        if let (Some(region), Some(connection)) = (self.region, &self.connection) {
+            let (height, width) = wayshot_utils::canvas_size(connection)?;
+            if !region.fits(height, width) {
+                return Err(Error::RegionOutOfBounds.logged(format!(
+                    "{region} does not fit in the display ({width}x{height})"
+                )));
+            }
+        }
+
+        let format = self.frame_format()?;
+        self.tracker.announce(format);
+
+        Ok(())
+    }
+
+    fn capture(&mut self) -> Result<DynamicImage, Error> {
+        if let Some(connection) = &mut self.connection {
+            // Outputs may have been plugged, unplugged or resized since the last capture
+            let captured = connection
+                .refresh_outputs()
+                .and_then(|_| connection.screenshot_all(false));
+
+            match captured {
+                Ok(rgba_image) => return Ok(rgba_image),
+                Err(err) => {
+                    log::warn!("Capture failed ({err}), reconnecting to the compositor...");
+                    self.connection = None;
+                }
+            }
+        }
+
+        let connection = wayshot_utils::connect()?;
+        let rgba_image = connection
+            .screenshot_all(false)
+            .map_err(|err| Error::CaptureFailed.logged(err))?;
+        self.connection = Some(connection);
+
+        Ok(rgba_image)
+    }
 }
 
 pub mod wayshot_utils {
     use super::*;
-    pub fn display_size() -> (u32, u32) {
-        let wayshot_connection = WayshotConnection::new().unwrap();
-        let dimensions = &wayshot_connection
-            .get_all_outputs()
-            .first()
-            .unwrap()
-            .physical_size;
 
-        (dimensions.height, dimensions.width)
+    pub fn connect() -> Result<WayshotConnection, Error> {
+        WayshotConnection::new().map_err(|err| Error::BackendUnavailable.logged(err))
+    }
+
+    /// Size of the canvas all outputs are blended into, in physical pixels
+    pub fn display_size() -> Result<(u32, u32), Error> {
+        canvas_size(&connect()?)
+    }
+
+    pub fn canvas_size(connection: &WayshotConnection) -> Result<(u32, u32), Error> {
+        let outputs = connection.get_all_outputs();
+
+        if outputs.is_empty() {
+            return Err(Error::MonitorNotFound.logged("no Wayland outputs available"));
+        }
+
+        Ok(display_layout(outputs).canvas_size())
+    }
+
+    /// Physical pixels per logical pixel of an output, whichever way it is rotated
+    pub fn output_scale(output: &OutputInfo) -> f64 {
+        let logical = output.logical_region.inner.size;
+        let physical = output.physical_size;
+        match logical.width.max(logical.height) {
+            0 => 1.0,
+            logical_side => physical.width.max(physical.height) as f64 / logical_side as f64,
+        }
+    }
+
+    /// Outputs are composited at the highest scale among them
+    pub fn display_scale(outputs: &[OutputInfo]) -> f64 {
+        outputs
+            .iter()
+            .map(output_scale)
+            .reduce(f64::max)
+            .unwrap_or(1.0)
+    }
+
+    /// Outputs are laid out in logical coordinates, which the frames hold at the display scale
+    pub fn output_geometry(output: &OutputInfo, scale: f64) -> MonitorGeometry {
+        let region = &output.logical_region.inner;
+        let physical = |logical: f64| (logical * scale).round();
+        MonitorGeometry {
+            name: output.name.clone(),
+            x: physical(region.position.x as f64) as i32,
+            y: physical(region.position.y as f64) as i32,
+            width: physical(region.size.width as f64) as u32,
+            height: physical(region.size.height as f64) as u32,
+        }
+    }
+
+    pub fn display_layout(outputs: &[OutputInfo]) -> DisplayLayout {
+        let scale = display_scale(outputs);
+        DisplayLayout {
+            monitors: outputs
+                .iter()
+                .map(|output| output_geometry(output, scale))
+                .collect(),
+        }
     }
 }
 
@@ -30,13 +168,28 @@ impl<K, F> FrameProcessor<F> for WayshotCapturer<K>
 where
     F: Send + 'static,
     K: Send + Copy,
-    F: PullableFrameProperties<K, BytesMut>,
+    F: PullableFrameProperties<K, BytesMut>
+        + FrameProperties<K, FrameFormat>
+        + FrameProperties<K, GeometryChange>
+        + FrameError<Error>,
 {
     async fn process(&mut self, mut dto: F) -> Option<F> {
         // Capture screen data
         log::debug!("Capturing screen data...");
-        let wayshot_connection = WayshotConnection::new().unwrap();
-        let rgba_image = wayshot_connection.screenshot_all(false).unwrap();
+        // Cropping happens on the physical pixels, as logical regions would be rescaled on HiDPI outputs
+        let captured = self.capture().and_then(|image| match self.region {
+            Some(region) => region
+                .crop(&image.into_rgba8())
+                .map(DynamicImage::ImageRgba8),
+            None => Ok(image),
+        });
+        let rgba_image = match captured {
+            Ok(rgba_image) => rgba_image,
+            Err(error) => {
+                dto.report_error(error);
+                return Some(dto);
+            }
+        };
 
         // Remove the alpha channel
         log::debug!("Removing alpha channel...");
@@ -44,9 +197,10 @@ where
 
         // Write data into the DTO buffer
         log::debug!("Writing data to DTO...");
-        let mut buffer = dto
-            .pull(&self.buffer_key)
-            .expect("No buffer to pull from frame data");
+        let Some(mut buffer) = dto.pull(&self.buffer_key) else {
+            dto.report_error(Error::MissingBuffer);
+            return Some(dto);
+        };
 
         buffer.clear();
         log::debug!("Buffer len before write: {}", buffer.len());
@@ -54,6 +208,11 @@ where
         log::debug!("Buffer len after write: {}", buffer.len());
 
         dto.push(self.buffer_key, buffer);
+        self.tracker.declare(
+            &mut dto,
+            self.buffer_key,
+            FrameFormat::packed(PixelFormat::Rgb8, rgb_image.width(), rgb_image.height()),
+        );
 
         // Return the filled DTO
         log::debug!("Done");
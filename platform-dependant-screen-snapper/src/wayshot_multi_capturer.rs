@@ -0,0 +1,242 @@
+use std::fmt::Display;
+
+use async_trait::async_trait;
+use bon::Builder;
+use image::{DynamicImage, RgbaImage};
+use libwayshot::{WayshotConnection, output::OutputInfo};
+use remotia::{
+    buffers::{BufMut, BytesMut},
+    traits::{FrameError, FrameProcessor, FrameProperties, PullableFrameProperties},
+};
+
+use crate::{
+    error::Error,
+    geometry::{GeometryChange, GeometryTracker},
+    layout::{DisplayLayout, MultiMonitorMode},
+    pixel_format::{FrameFormat, PixelFormat},
+    wayshot_capturer::wayshot_utils,
+};
+
+#[derive(Builder)]
+pub struct WayshotMultiCapturer<K> {
+    #[builder(skip)]
+    connection: Option<WayshotConnection>,
+
+    #[builder(skip)]
+    outputs: Vec<OutputInfo>,
+
+    /// Layout of the outputs when the capturer was opened
+    #[builder(skip)]
+    layout: DisplayLayout,
+
+    /// One per buffer written
+    #[builder(skip)]
+    trackers: Vec<GeometryTracker>,
+
+    mode: MultiMonitorMode<K>,
+}
+
+impl<K> WayshotMultiCapturer<K> {
+    pub fn mode(&self) -> &MultiMonitorMode<K> {
+        &self.mode
+    }
+
+    /// Layout the buffers are sized after, known once the capturer is opened
+    pub fn layout(&self) -> &DisplayLayout {
+        &self.layout
+    }
+
+    /// Opens the Wayland connection ahead of the first capture and records the outputs layout
+    pub fn open(&mut self) -> Result<(), Error> {
+        if self.connection.is_none() {
+            self.connection = Some(wayshot_utils::connect()?);
+        }
+
+        if self.layout.monitors.is_empty() {
+            self.refresh()?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the outputs again, as they may have been plugged, unplugged or resized
+    fn refresh(&mut self) -> Result<(), Error> {
+        let connection = self.connection.as_mut().ok_or(Error::BackendUnavailable)?;
+        connection
+            .refresh_outputs()
+            .map_err(|err| Error::CaptureFailed.logged(err))?;
+
+        let outputs = connection.get_all_outputs().to_vec();
+        let outputs = self.select_outputs(&outputs)?;
+        let layout = wayshot_utils::display_layout(&outputs);
+
+        if !self.layout.monitors.is_empty() && layout != self.layout {
+            log::info!("Output layout changed to {:?}", layout.monitors);
+        }
+
+        self.outputs = outputs;
+        self.layout = layout;
+
+        Ok(())
+    }
+
+    fn select_outputs(&self, outputs: &[OutputInfo]) -> Result<Vec<OutputInfo>, Error> {
+        let mut outputs = outputs.to_vec();
+
+        if let MultiMonitorMode::PerMonitor(keys) = &self.mode {
+            if keys.len() > outputs.len() {
+                return Err(Error::MonitorNotFound.logged(format!(
+                    "{} buffers for {} outputs",
+                    keys.len(),
+                    outputs.len()
+                )));
+            }
+
+            outputs.truncate(keys.len());
+        }
+
+        if outputs.is_empty() {
+            return Err(Error::MonitorNotFound.logged("no Wayland outputs available"));
+        }
+
+        Ok(outputs)
+    }
+
+    fn capture(&mut self) -> Result<Vec<RgbaImage>, Error> {
+        if self.connection.is_some() {
+            let captured = self.refresh().and_then(|_| {
+                let connection = self.connection.as_ref().ok_or(Error::BackendUnavailable)?;
+                self.capture_outputs(connection)
+            });
+
+            match captured {
+                Ok(rgba_images) => return Ok(rgba_images),
+                Err(_) => {
+                    log::warn!("Capture failed, reconnecting to the compositor...");
+                    self.connection = None;
+                }
+            }
+        }
+
+        self.connection = Some(wayshot_utils::connect()?);
+        self.refresh()?;
+
+        let connection = self.connection.as_ref().ok_or(Error::BackendUnavailable)?;
+        self.capture_outputs(connection)
+    }
+
+    fn capture_outputs(&self, connection: &WayshotConnection) -> Result<Vec<RgbaImage>, Error> {
+        match self.mode {
+            // The compositor already blends the outputs following their positions
+            MultiMonitorMode::Stitched(_) => {
+                Ok(vec![screenshot(connection.screenshot_all(false))?])
+            }
+            MultiMonitorMode::PerMonitor(_) => self
+                .outputs
+                .iter()
+                .map(|output| screenshot(connection.screenshot(output.logical_region, false)))
+                .collect(),
+        }
+    }
+
+    fn write<F>(&mut self, dto: &mut F, rgba_images: Vec<RgbaImage>) -> Result<(), Error>
+    where
+        K: Copy,
+        F: PullableFrameProperties<K, BytesMut>
+            + FrameProperties<K, FrameFormat>
+            + FrameProperties<K, GeometryChange>,
+    {
+        match &self.mode {
+            MultiMonitorMode::Stitched(buffer_key) => {
+                self.trackers.resize_with(1, Default::default);
+
+                let (height, width) = self.layout.canvas_size();
+                let Some(canvas) = rgba_images
+                    .into_iter()
+                    .next()
+                    .filter(|canvas| canvas.dimensions() == (width, height))
+                else {
+                    return Err(Error::SizeMismatch.logged(format!(
+                        "composited frame does not match the {width}x{height} layout"
+                    )));
+                };
+
+                write_buffer(dto, *buffer_key, &mut self.trackers[0], canvas)
+            }
+            MultiMonitorMode::PerMonitor(buffer_keys) => {
+                self.trackers
+                    .resize_with(buffer_keys.len(), Default::default);
+
+                for (index, (buffer_key, rgba_image)) in
+                    buffer_keys.iter().zip(rgba_images).enumerate()
+                {
+                    self.layout.check(index, &rgba_image)?;
+                    write_buffer(dto, *buffer_key, &mut self.trackers[index], rgba_image)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+fn screenshot<E: Display>(result: Result<DynamicImage, E>) -> Result<RgbaImage, Error> {
+    result
+        .map(DynamicImage::into_rgba8)
+        .map_err(|err| Error::CaptureFailed.logged(err))
+}
+
+fn write_buffer<K, F>(
+    dto: &mut F,
+    buffer_key: K,
+    tracker: &mut GeometryTracker,
+    rgba_image: RgbaImage,
+) -> Result<(), Error>
+where
+    K: Copy,
+    F: PullableFrameProperties<K, BytesMut>
+        + FrameProperties<K, FrameFormat>
+        + FrameProperties<K, GeometryChange>,
+{
+    // Remove the alpha channel
+    let rgb_image = DynamicImage::ImageRgba8(rgba_image).into_rgb8();
+
+    let mut buffer = dto.pull(&buffer_key).ok_or(Error::MissingBuffer)?;
+    buffer.clear();
+    buffer.put_slice(rgb_image.as_raw());
+    dto.push(buffer_key, buffer);
+    tracker.declare(
+        dto,
+        buffer_key,
+        FrameFormat::packed(PixelFormat::Rgb8, rgb_image.width(), rgb_image.height()),
+    );
+
+    Ok(())
+}
+
+#[async_trait]
+impl<K, F> FrameProcessor<F> for WayshotMultiCapturer<K>
+where
+    F: Send + 'static,
+    K: Send + Copy,
+    F: PullableFrameProperties<K, BytesMut>
+        + FrameProperties<K, FrameFormat>
+        + FrameProperties<K, GeometryChange>
+        + FrameError<Error>,
+{
+    async fn process(&mut self, mut dto: F) -> Option<F> {
+        // Capture screen data
+        log::debug!("Capturing {} outputs...", self.layout.monitors.len());
+        let result = self
+            .capture()
+            .and_then(|rgba_images| self.write(&mut dto, rgba_images));
+
+        if let Err(error) = result {
+            dto.report_error(error);
+        }
+
+        // Return the filled DTO
+        log::debug!("Done");
+        Some(dto)
+    }
+}
@@ -0,0 +1,202 @@
+use std::{
+    collections::VecDeque,
+    panic::{self, AssertUnwindSafe},
+    str::FromStr,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    thread::{self, JoinHandle},
+};
+
+use tokio::sync::Notify;
+
+pub const DEFAULT_WORKERS: usize = 1;
+pub const DEFAULT_QUEUE_SIZE: usize = 4;
+
+/// What to do with a job submitted while the queue is full
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QueueFullPolicy {
+    /// Wait for a free slot, holding back the pipeline
+    #[default]
+    Block,
+    /// Discard the oldest queued job to make room
+    DropOldest,
+}
+
+impl FromStr for QueueFullPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "block" => Ok(Self::Block),
+            "drop-oldest" => Ok(Self::DropOldest),
+            _ => Err(format!("Unknown queue full policy: {value}")),
+        }
+    }
+}
+
+struct Job {
+    description: String,
+    run: Box<dyn FnOnce() + Send>,
+}
+
+#[derive(Default)]
+struct State {
+    queue: VecDeque<Job>,
+    /// Queued and running jobs
+    pending: usize,
+    closed: bool,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    capacity: usize,
+    on_full: QueueFullPolicy,
+    /// Wakes up the workers when a job is queued or the pool is closed
+    queued: Condvar,
+    /// Wakes up the submitters when a job leaves the queue
+    dequeued: Notify,
+    /// Wakes up the flushers when no job is pending
+    idle: Notify,
+}
+
+impl Shared {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// Closes the pool once the last handle is dropped, waiting for the pending jobs
+struct Workers {
+    shared: Arc<Shared>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl Drop for Workers {
+    fn drop(&mut self) {
+        self.shared.state().closed = true;
+        self.shared.queued.notify_all();
+
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Bounded queue of blocking jobs, such as encoding and writing images,
+/// run by dedicated threads outside of the async runtime
+#[derive(Clone)]
+pub struct WorkerPool {
+    shared: Arc<Shared>,
+    _workers: Arc<Workers>,
+}
+
+impl WorkerPool {
+    pub fn new(workers: usize, capacity: usize, on_full: QueueFullPolicy) -> Self {
+        let shared = Arc::new(Shared {
+            state: Mutex::default(),
+            capacity: capacity.max(1),
+            on_full,
+            queued: Condvar::new(),
+            dequeued: Notify::new(),
+            idle: Notify::new(),
+        });
+
+        let threads = (0..workers.max(1))
+            .map(|index| {
+                let shared = shared.clone();
+                thread::Builder::new()
+                    .name(format!("worker-{index}"))
+                    .spawn(move || work(&shared))
+                    .expect("Unable to spawn a worker thread")
+            })
+            .collect();
+
+        Self {
+            shared: shared.clone(),
+            _workers: Arc::new(Workers { shared, threads }),
+        }
+    }
+
+    /// Queues a job, waiting for a free slot or dropping the oldest job if the queue is full
+    pub async fn submit(&self, description: String, run: impl FnOnce() + Send + 'static) {
+        let mut job = Some(Job {
+            description,
+            run: Box::new(run),
+        });
+
+        loop {
+            // Registered before checking the queue, so that no wake-up is missed
+            let dequeued = self.shared.dequeued.notified();
+
+            {
+                let mut state = self.shared.state();
+                if state.queue.len() >= self.shared.capacity {
+                    match self.shared.on_full {
+                        QueueFullPolicy::Block => {}
+                        QueueFullPolicy::DropOldest => {
+                            if let Some(dropped) = state.queue.pop_front() {
+                                state.pending -= 1;
+                                log::warn!("Queue full, dropped {}", dropped.description);
+                            }
+                        }
+                    }
+                }
+
+                if state.queue.len() < self.shared.capacity {
+                    state.queue.extend(job.take());
+                    state.pending += 1;
+                    self.shared.queued.notify_one();
+                    return;
+                }
+            }
+
+            log::debug!("Queue full, waiting for a worker");
+            dequeued.await;
+        }
+    }
+
+    /// Waits until all the submitted jobs have run
+    pub async fn flush(&self) {
+        loop {
+            let idle = self.shared.idle.notified();
+            if self.shared.state().pending == 0 {
+                return;
+            }
+
+            idle.await;
+        }
+    }
+}
+
+fn work(shared: &Shared) {
+    loop {
+        let job = {
+            let mut state = shared.state();
+            loop {
+                if let Some(job) = state.queue.pop_front() {
+                    break job;
+                }
+
+                if state.closed {
+                    return;
+                }
+
+                state = shared
+                    .queued
+                    .wait(state)
+                    .unwrap_or_else(|err| err.into_inner());
+            }
+        };
+        shared.dequeued.notify_waiters();
+
+        log::debug!("Running {}", job.description);
+        if panic::catch_unwind(AssertUnwindSafe(job.run)).is_err() {
+            log::error!("Worker panicked while running {}", job.description);
+        }
+
+        let mut state = shared.state();
+        state.pending -= 1;
+        if state.pending == 0 {
+            shared.idle.notify_waiters();
+        }
+    }
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use common::TestFrame;
+use platform_dependant_screen_snapper::{
+    change_detector::{ChangeDetector, ChangeMetric, ChangeScore},
+    error::Error,
+    geometry::GeometryChange,
+    pixel_format::{FrameFormat, PixelFormat},
+};
+use remotia::traits::FrameProcessor;
+
+const SIZE: u32 = 8;
+
+/// Frame whose brightness grows from left to right, offset by `shift`
+fn gradient(shift: u8) -> Vec<u8> {
+    (0..SIZE * SIZE)
+        .flat_map(|index| {
+            let value = (index % SIZE) as u8 * 30 + shift;
+            [value, value, value]
+        })
+        .collect()
+}
+
+fn detector(metric: ChangeMetric, threshold: f64) -> ChangeDetector<()> {
+    ChangeDetector::builder()
+        .buffer_key(())
+        .metric(metric)
+        .threshold(threshold)
+        .build()
+}
+
+async fn check(detector: &mut ChangeDetector<()>, pixels: &[u8]) -> (f64, Option<Error>) {
+    let format = FrameFormat::packed(PixelFormat::Rgb8, SIZE, SIZE);
+    let frame = detector
+        .process(TestFrame::new(format, pixels))
+        .await
+        .unwrap();
+    let ChangeScore(score) = frame.change_score.unwrap();
+    (score, frame.error)
+}
+
+#[tokio::test]
+async fn exact_hash_drops_identical_frames() {
+    let mut detector = detector(ChangeMetric::ExactHash, 0.5);
+
+    assert_eq!(check(&mut detector, &gradient(0)).await, (1.0, None));
+    assert_eq!(
+        check(&mut detector, &gradient(0)).await,
+        (0.0, Some(Error::Unchanged))
+    );
+
+    let mut pixels = gradient(0);
+    pixels[0] += 1;
+    assert_eq!(check(&mut detector, &pixels).await, (1.0, None));
+}
+
+#[tokio::test]
+async fn pixel_ratio_scores_the_share_of_changed_pixels() {
+    let mut detector = detector(ChangeMetric::PixelRatio, 0.1);
+    check(&mut detector, &gradient(0)).await;
+
+    // 4 pixels out of 64 stay under the threshold
+    let mut pixels = gradient(0);
+    pixels[..12].fill(255);
+    assert_eq!(
+        check(&mut detector, &pixels).await,
+        (4.0 / 64.0, Some(Error::Unchanged))
+    );
+
+    // Compared with the last kept frame rather than the dropped one
+    pixels[12..24].fill(255);
+    assert_eq!(check(&mut detector, &pixels).await, (8.0 / 64.0, None));
+    assert_eq!(
+        check(&mut detector, &pixels).await,
+        (0.0, Some(Error::Unchanged))
+    );
+}
+
+#[tokio::test]
+async fn perceptual_hash_ignores_small_changes() {
+    let mut detector = detector(ChangeMetric::PerceptualHash, 0.25);
+    check(&mut detector, &gradient(0)).await;
+
+    // The same picture, a bit brighter
+    assert_eq!(
+        check(&mut detector, &gradient(10)).await,
+        (0.0, Some(Error::Unchanged))
+    );
+
+    // The brightness now decreases from left to right
+    let mirrored: Vec<u8> = gradient(0)
+        .chunks(SIZE as usize * 3)
+        .flat_map(|row| row.rchunks(3).flatten().copied().collect::<Vec<_>>())
+        .collect();
+    let (score, error) = check(&mut detector, &mirrored).await;
+    assert!(score > 0.5, "mirrored frame scored {score}");
+    assert_eq!(error, None);
+}
+
+#[tokio::test]
+async fn geometry_changes_discard_the_reference() {
+    let mut detector = detector(ChangeMetric::ExactHash, 0.5);
+    check(&mut detector, &gradient(0)).await;
+
+    let format = FrameFormat::packed(PixelFormat::Rgb8, SIZE, SIZE);
+    let mut frame = TestFrame::new(format, &gradient(0));
+    frame.geometry_change = Some(GeometryChange {
+        previous: FrameFormat::packed(PixelFormat::Rgb8, SIZE * 2, SIZE),
+        current: format,
+    });
+
+    let frame = detector.process(frame).await.unwrap();
+    assert_eq!(frame.change_score, Some(ChangeScore(1.0)));
+    assert_eq!(frame.error, None);
+}
@@ -0,0 +1,63 @@
+use platform_dependant_screen_snapper::control::ControlCommand;
+
+#[test]
+fn parses_each_command() {
+    let commands = [
+        ("pause", ControlCommand::Pause),
+        ("resume", ControlCommand::Resume),
+        ("snap-now", ControlCommand::SnapNow),
+        ("set-interval 1500", ControlCommand::SetInterval(1500)),
+        ("status", ControlCommand::Status),
+        ("shutdown", ControlCommand::Shutdown),
+    ];
+
+    for (line, command) in commands {
+        assert_eq!(line.parse(), Ok(command));
+        assert_eq!(command.to_string(), line);
+    }
+
+    assert_eq!(
+        "  set-interval\t250 \r".parse(),
+        Ok(ControlCommand::SetInterval(250))
+    );
+}
+
+#[test]
+fn rejects_malformed_commands() {
+    for line in [
+        "",
+        "stop",
+        "Pause",
+        "pause now",
+        "set-interval",
+        "set-interval 0",
+        "set-interval -5",
+        "set-interval 1s",
+        "set-interval 10 20",
+    ] {
+        assert!(line.parse::<ControlCommand>().is_err(), "{line:?} parsed");
+    }
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn answers_commands_over_the_socket() {
+    use platform_dependant_screen_snapper::control::{ControlServer, Controller, send_command};
+
+    let path = std::env::temp_dir().join(format!("screen-snapper-control-{}", std::process::id()));
+    let server = ControlServer::bind(&path, Controller::new(1000)).unwrap();
+    let controller = server.controller().clone();
+    let serving = tokio::spawn(server.run());
+
+    let reply = send_command(&path, ControlCommand::SetInterval(250)).await;
+    assert_eq!(reply.unwrap(), "ok interval set to 250 ms");
+    assert!(
+        controller
+            .apply(ControlCommand::Status)
+            .contains("interval 250 ms")
+    );
+
+    serving.abort();
+    let _ = serving.await;
+    assert!(!path.exists(), "socket left behind");
+}
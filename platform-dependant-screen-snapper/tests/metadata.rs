@@ -0,0 +1,165 @@
+use std::io::Cursor;
+
+use chrono::{Local, TimeZone};
+use platform_dependant_screen_snapper::{
+    image_saver::ImageFormat,
+    layout::MonitorGeometry,
+    metadata::{CaptureSource, SOFTWARE, SnapshotMetadata},
+    pixel_format::PixelFormat,
+};
+
+fn metadata() -> SnapshotMetadata {
+    let source = CaptureSource {
+        backend: Some("xcap".to_string()),
+        monitor: Some(MonitorGeometry {
+            name: "東京-1".to_string(),
+            x: -1920,
+            y: 0,
+            width: 4,
+            height: 2,
+        }),
+    };
+
+    SnapshotMetadata::new(
+        "1.png".to_string(),
+        Local.with_ymd_and_hms(2024, 3, 9, 14, 5, 7).unwrap(),
+        "desk",
+        &source,
+        PixelFormat::Bgra8,
+        4,
+        2,
+    )
+}
+
+/// Encodes a small frame and embeds the metadata, as the saver does
+fn written(format: &str) -> Vec<u8> {
+    let format: ImageFormat = format.parse().unwrap();
+    let mut encoded = Vec::new();
+    format
+        .encode(Cursor::new(&mut encoded), &[128; 4 * 2 * 3], 4, 2)
+        .unwrap();
+    metadata().embed(&format, &mut encoded);
+
+    image::load_from_memory(&encoded).expect("the file no longer decodes");
+    encoded
+}
+
+/// Type and data of the chunks of a PNG file, checking their CRC
+fn png_chunks(png: &[u8]) -> Vec<(String, Vec<u8>)> {
+    let mut chunks = Vec::new();
+    let mut offset = 8;
+    while offset < png.len() {
+        let length = u32::from_be_bytes(png[offset..offset + 4].try_into().unwrap()) as usize;
+        let chunk_type = &png[offset + 4..offset + 8];
+        let data = &png[offset + 8..offset + 8 + length];
+        let crc = u32::from_be_bytes(
+            png[offset + 8 + length..offset + 12 + length]
+                .try_into()
+                .unwrap(),
+        );
+
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(chunk_type);
+        hasher.update(data);
+        assert_eq!(hasher.finalize(), crc);
+
+        chunks.push((
+            String::from_utf8(chunk_type.to_vec()).unwrap(),
+            data.to_vec(),
+        ));
+        offset += 12 + length;
+    }
+
+    chunks
+}
+
+#[test]
+fn png_holds_text_chunks() {
+    let chunks = png_chunks(&written("png"));
+    assert_eq!(chunks.last().unwrap().0, "IEND");
+
+    let text = |keyword: &str| {
+        chunks.iter().find_map(|(chunk_type, data)| {
+            let (key, value) = data.split_at(data.iter().position(|&byte| byte == 0)?);
+            (key == keyword.as_bytes()).then(|| match chunk_type.as_str() {
+                "tEXt" => (
+                    chunk_type.clone(),
+                    value[1..].iter().map(|&byte| byte as char).collect(),
+                ),
+                "iTXt" => (
+                    chunk_type.clone(),
+                    String::from_utf8(value[5..].to_vec()).unwrap(),
+                ),
+                _ => panic!("{keyword} stored in a {chunk_type} chunk"),
+            })
+        })
+    };
+
+    let latin1 = |value: &str| Some(("tEXt".to_string(), value.to_string()));
+    let unicode = |value: &str| Some(("iTXt".to_string(), value.to_string()));
+
+    assert_eq!(text("Software"), latin1(SOFTWARE));
+    assert_eq!(text("Host"), latin1("desk"));
+    assert_eq!(text("Pixel Format"), latin1("bgra8"));
+    assert_eq!(text("Backend"), latin1("xcap"));
+    assert_eq!(text("Monitor Geometry"), latin1("4x2-1920+0"));
+    assert_eq!(text("Monitor"), unicode("東京-1"));
+    assert_eq!(
+        text("Description"),
+        unicode("東京-1 4x2-1920+0 via xcap, captured as bgra8")
+    );
+    assert_eq!(
+        text("Creation Time").map(|(_, value)| value),
+        Some(metadata().time.to_rfc3339())
+    );
+}
+
+#[test]
+fn jpeg_holds_an_exif_segment() {
+    let jpeg = written("jpg");
+    assert_eq!(&jpeg[..2], [0xff, 0xd8]);
+
+    // The APP1 segment follows the SOI marker and the JFIF segment, if any
+    let mut offset = 2;
+    if jpeg[2..4] == [0xff, 0xe0] {
+        offset += 2 + u16::from_be_bytes([jpeg[4], jpeg[5]]) as usize;
+    }
+    assert_eq!(jpeg[offset..offset + 2], [0xff, 0xe1]);
+    let length = u16::from_be_bytes([jpeg[offset + 2], jpeg[offset + 3]]) as usize;
+    let segment = &jpeg[offset + 4..offset + 2 + length];
+    assert_eq!(&segment[..6], b"Exif\0\0");
+
+    let tiff = &segment[6..];
+    assert_eq!(&tiff[..4], b"MM\0\x2a");
+    let u16_at = |offset: usize| u16::from_be_bytes([tiff[offset], tiff[offset + 1]]);
+    let u32_at = |offset: usize| u32::from_be_bytes(tiff[offset..offset + 4].try_into().unwrap());
+
+    let ifd = u32_at(4) as usize;
+    let fields: Vec<(u16, String)> = (0..u16_at(ifd) as usize)
+        .map(|index| {
+            let entry = ifd + 2 + index * 12;
+            assert_eq!(u16_at(entry + 2), 2, "ASCII type");
+            let count = u32_at(entry + 4) as usize;
+            let value = match count {
+                ..=4 => &tiff[entry + 8..entry + 8 + count],
+                _ => &tiff[u32_at(entry + 8) as usize..][..count],
+            };
+            assert_eq!(value.last(), Some(&0));
+            let text = String::from_utf8(value[..count - 1].to_vec()).unwrap();
+            (u16_at(entry), text)
+        })
+        .collect();
+
+    assert_eq!(
+        fields,
+        [
+            (
+                0x010e,
+                "??-1 4x2-1920+0 via xcap, captured as bgra8".to_string()
+            ),
+            (0x0131, SOFTWARE.to_string()),
+            (0x0132, "2024:03:09 14:05:07".to_string()),
+            (0x013c, "desk".to_string()),
+        ]
+    );
+}
@@ -0,0 +1,81 @@
+use platform_dependant_screen_snapper::pixel_format::{FrameFormat, PixelFormat};
+
+const FORMATS: [PixelFormat; 3] = [PixelFormat::Rgb8, PixelFormat::Rgba8, PixelFormat::Bgra8];
+
+/// 3x2 frame of distinct opaque colors
+fn colors() -> Vec<[u8; 3]> {
+    (0..6u8)
+        .map(|index| [index * 40, 255 - index * 30, index * 7])
+        .collect()
+}
+
+fn encode(pixel_format: PixelFormat, colors: &[[u8; 3]]) -> Vec<u8> {
+    colors
+        .iter()
+        .flat_map(|&color| {
+            let mut pixel = vec![0; pixel_format.bytes_per_pixel()];
+            pixel_format.write(&mut pixel, color);
+            pixel
+        })
+        .collect()
+}
+
+#[test]
+fn converts_back_and_forth_between_formats() {
+    let colors = colors();
+
+    for source in FORMATS {
+        for target in FORMATS {
+            let format = FrameFormat::packed(source, 3, 2);
+            let converted = format.convert(&encode(source, &colors), target);
+            assert_eq!(converted, encode(target, &colors), "{source} to {target}");
+
+            let back = FrameFormat::packed(target, 3, 2).convert(&converted, source);
+            assert_eq!(
+                back,
+                encode(source, &colors),
+                "{source} to {target} and back"
+            );
+        }
+    }
+}
+
+#[test]
+fn swaps_the_channels_and_sets_the_alpha_opaque() {
+    let format = FrameFormat::packed(PixelFormat::Bgra8, 1, 1);
+    assert_eq!(format.convert(&[1, 2, 3, 4], PixelFormat::Rgb8), [3, 2, 1]);
+    assert_eq!(
+        format.convert(&[1, 2, 3, 4], PixelFormat::Rgba8),
+        [3, 2, 1, 255]
+    );
+}
+
+#[test]
+fn drops_the_row_padding() {
+    let colors = colors();
+
+    for source in FORMATS {
+        let row_size = 3 * source.bytes_per_pixel();
+        let format = FrameFormat {
+            stride: row_size + 5,
+            ..FrameFormat::packed(source, 3, 2)
+        };
+
+        let packed = encode(source, &colors);
+        let mut padded = Vec::new();
+        for row in packed.chunks(row_size) {
+            padded.extend_from_slice(row);
+            padded.extend_from_slice(&[0xAA; 5]);
+        }
+        assert!(format.fits(&padded));
+        assert!(!format.is_packed());
+
+        for target in FORMATS {
+            assert_eq!(
+                format.convert(&padded, target),
+                encode(target, &colors),
+                "padded {source} to {target}"
+            );
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use std::{
+    fs,
+    path::PathBuf,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use platform_dependant_screen_snapper::{
+    error::Error,
+    s3_storage::{RetryPolicy, S3Sink},
+    storage::{FileSystemSink, MemorySink, ReadPosition, StorageSink, StoredObject},
+};
+use tiny_http::Server;
+
+#[path = "../examples/object-store/store.rs"]
+mod store;
+
+const BACKOFF: Duration = Duration::from_millis(50);
+
+/// Object store stand-in on a free port, answering with 503 every `fail_every` requests
+struct StandIn {
+    endpoint: String,
+    root: PathBuf,
+}
+
+impl StandIn {
+    fn start(name: &str, fail_every: Option<u64>) -> Self {
+        let root =
+            std::env::temp_dir().join(format!("screen-snapper-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("snapshots")).unwrap();
+
+        let server = Arc::new(Server::http("127.0.0.1:0").unwrap());
+        let endpoint = format!("http://{}", server.server_addr());
+
+        let served = root.clone();
+        thread::spawn(move || store::serve(&server, &served, fail_every));
+
+        Self { endpoint, root }
+    }
+
+    fn sink(&self, attempts: u32) -> S3Sink {
+        S3Sink::builder()
+            .endpoint(&self.endpoint)
+            .bucket("snapshots")
+            .prefix("host/")
+            .retry(RetryPolicy {
+                attempts,
+                initial_backoff: BACKOFF,
+                max_backoff: BACKOFF,
+            })
+            .build()
+    }
+}
+
+impl Drop for StandIn {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+fn keys(sink: &dyn StorageSink) -> Vec<String> {
+    let mut keys: Vec<String> = sink
+        .list()
+        .unwrap()
+        .into_iter()
+        .map(|object| object.key)
+        .collect();
+    keys.sort();
+    keys
+}
+
+#[test]
+fn s3_sink_round_trip() {
+    let stand_in = StandIn::start("round-trip", None);
+    let sink = stand_in.sink(1);
+
+    sink.put("1.png", b"first").unwrap();
+    sink.put("2.png", b"second").unwrap();
+    sink.append("index.jsonl", b"{\"file\":\"1.png\"}\n")
+        .unwrap();
+    sink.append("index.jsonl", b"{\"file\":\"2.png\"}\n")
+        .unwrap();
+
+    assert!(sink.exists("1.png").unwrap());
+    assert!(!sink.exists("3.png").unwrap());
+    assert_eq!(sink.read("2.png").unwrap().as_deref(), Some(&b"second"[..]));
+    assert_eq!(sink.read("3.png").unwrap(), None);
+    assert_eq!(
+        sink.read("index.jsonl").unwrap().as_deref(),
+        Some(&b"{\"file\":\"1.png\"}\n{\"file\":\"2.png\"}\n"[..])
+    );
+
+    // Appended parts are kept out of the listing of the snapshots
+    assert_eq!(keys(&sink), ["1.png", "2.png"]);
+    assert!(sink.list().unwrap().contains(&StoredObject {
+        key: "2.png".to_string(),
+        size: 6,
+    }));
+    assert_eq!(
+        fs::read(stand_in.root.join("snapshots/host/1.png")).unwrap(),
+        b"first"
+    );
+
+    sink.delete("1.png").unwrap();
+    sink.delete("3.png").unwrap();
+    assert_eq!(keys(&sink), ["2.png"]);
+}
+
+#[test]
+fn s3_sink_reads_appended_lines_incrementally() {
+    let stand_in = StandIn::start("lines", None);
+    let sink = stand_in.sink(1);
+
+    let (lines, position) = sink
+        .read_lines("index.jsonl", &ReadPosition::default())
+        .unwrap();
+    assert!(lines.is_empty());
+
+    sink.append("index.jsonl", b"one\n").unwrap();
+    sink.append("index.jsonl", b"two\n").unwrap();
+    let (lines, position) = sink.read_lines("index.jsonl", &position).unwrap();
+    assert_eq!(lines, ["one", "two"]);
+
+    sink.append("index.jsonl", b"three\n").unwrap();
+    let (lines, position) = sink.read_lines("index.jsonl", &position).unwrap();
+    assert_eq!(lines, ["three"]);
+
+    let (lines, position) = sink.read_lines("index.jsonl", &position).unwrap();
+    assert!(lines.is_empty());
+
+    // A part uploaded late, under an earlier name than those read already
+    sink.put(
+        &format!(
+            "{}00000000T000000Z-000000",
+            S3Sink::parts_prefix("index.jsonl")
+        ),
+        b"late\n",
+    )
+    .unwrap();
+    let (lines, _) = sink.read_lines("index.jsonl", &position).unwrap();
+    assert_eq!(lines, ["late"]);
+
+    // The parts read are merged into the index
+    let parts = stand_in.root.join("snapshots/host/index.jsonl.parts");
+    assert_eq!(fs::read_dir(parts).unwrap().count(), 0);
+    assert_eq!(
+        sink.read("index.jsonl").unwrap().as_deref(),
+        Some(&b"one\ntwo\nthree\nlate\n"[..])
+    );
+}
+
+#[test]
+fn s3_sink_retries_failed_requests() {
+    // Every other request fails, so that the second upload succeeds on its second attempt
+    let stand_in = StandIn::start("retry", Some(2));
+    let sink = stand_in.sink(2);
+
+    let started = Instant::now();
+    sink.put("1.png", b"first").unwrap();
+    sink.put("2.png", b"second").unwrap();
+
+    assert!(started.elapsed() >= BACKOFF);
+    assert_eq!(
+        fs::read(stand_in.root.join("snapshots/host/2.png")).unwrap(),
+        b"second"
+    );
+}
+
+#[test]
+fn s3_sink_reports_failures_once_out_of_attempts() {
+    let stand_in = StandIn::start("out-of-attempts", Some(1));
+    let sink = stand_in.sink(3);
+
+    let started = Instant::now();
+    assert_eq!(sink.put("1.png", b"first"), Err(Error::UploadFailed));
+
+    // Waited between the three attempts
+    assert!(started.elapsed() >= BACKOFF * 2);
+    assert!(!stand_in.root.join("snapshots/host/1.png").exists());
+}
+
+#[test]
+fn memory_sink_round_trip() {
+    let sink = MemorySink::new();
+    let shared: Arc<dyn StorageSink> = Arc::new(sink.clone());
+
+    shared.put("1.png", b"first").unwrap();
+    shared.put("1.png", b"replaced").unwrap();
+    shared.append("index.jsonl", b"one\n").unwrap();
+    shared.append("index.jsonl", b"two\n").unwrap();
+
+    assert_eq!(sink.len(), 2);
+    assert!(shared.exists("1.png").unwrap());
+    assert!(!shared.exists("2.png").unwrap());
+    assert_eq!(keys(shared.as_ref()), ["1.png", "index.jsonl"]);
+    assert_eq!(sink.get("1.png").as_deref(), Some(&b"replaced"[..]));
+    assert_eq!(
+        shared.read("index.jsonl").unwrap().as_deref(),
+        Some(&b"one\ntwo\n"[..])
+    );
+    assert_eq!(shared.read("2.png").unwrap(), None);
+
+    // Lines still being written are left for the next read
+    let (lines, position) = shared
+        .read_lines("index.jsonl", &ReadPosition::default())
+        .unwrap();
+    assert_eq!(lines, ["one", "two"]);
+    shared.append("index.jsonl", b"thr").unwrap();
+    let (lines, position) = shared.read_lines("index.jsonl", &position).unwrap();
+    assert!(lines.is_empty());
+    shared.append("index.jsonl", b"ee\n").unwrap();
+    let (lines, _) = shared.read_lines("index.jsonl", &position).unwrap();
+    assert_eq!(lines, ["three"]);
+
+    shared.delete("1.png").unwrap();
+    assert!(sink.get("1.png").is_none());
+}
+
+#[test]
+fn file_system_sink_writes_the_same_key_concurrently() {
+    let directory =
+        std::env::temp_dir().join(format!("screen-snapper-concurrent-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    let sink = Arc::new(FileSystemSink::new(&directory));
+
+    let writers: Vec<_> = (0..8u8)
+        .map(|writer| {
+            let sink = sink.clone();
+            thread::spawn(move || {
+                for _ in 0..50 {
+                    sink.put("1.png", &[writer; 4096]).unwrap();
+                }
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+
+    // The object is one of the writes as a whole, and no temporary file is left behind
+    let data = fs::read(directory.join("1.png")).unwrap();
+    assert_eq!(data.len(), 4096);
+    assert!(data.iter().all(|&byte| byte == data[0]));
+    assert_eq!(fs::read_dir(&directory).unwrap().count(), 1);
+    assert!(sink.exists("1.png").unwrap());
+    assert!(!sink.exists("2.png").unwrap());
+
+    fs::remove_dir_all(directory).unwrap();
+}
@@ -4,10 +4,13 @@ use remotia::{
     pipeline::{component::Component, Pipeline},
     processors::functional::Function,
     render::winit::WinitRenderer,
-    traits::BorrowMutFrameProperties,
     transmission::receiver::TcpFrameReceiver,
 };
-use screen_mirror::{BufferType, FrameData};
+use screen_mirror::{
+    drop_failed_frames,
+    pixel_format::{FrameFormat, FrameFormatAdder, PixelFormat, PixelFormatConverter},
+    BufferType, FrameData,
+};
 use tokio::net::TcpStream;
 
 #[derive(Parser, Debug)]
@@ -20,6 +23,10 @@ struct Args {
 
     #[arg(long)]
     height: u32,
+
+    /// Pixel format of the frames sent by the server
+    #[arg(long, default_value = "bgra8")]
+    pixel_format: PixelFormat,
 }
 
 async fn establish_connection(address: String) -> TcpStream {
@@ -35,29 +42,30 @@ async fn main() {
 
     let socket = establish_connection(args.server_address).await;
 
+    let format = FrameFormat::packed(args.pixel_format, args.width, args.height);
+
     let handles = Pipeline::<FrameData>::new()
         .link(
             Component::new()
                 .append(BufferAllocator::new(
                     BufferType::RawFrameBuffer,
-                    (args.width * args.height * 4) as usize,
+                    format.buffer_size(),
                 ))
                 .append(TcpFrameReceiver::new(BufferType::RawFrameBuffer, socket))
-                .append(Function::new(|mut frame_data: FrameData| {
-                    log::debug!("Received frame data, changing channels order");
-
-                    let frame_buffer = frame_data.get_mut_ref(&BufferType::RawFrameBuffer).unwrap();
-                    for pixel in frame_buffer.chunks_mut(4) {
-                        let (r, g, b) = (pixel[0], pixel[1], pixel[2]);
-
-                        pixel[0] = b;
-                        pixel[1] = g;
-                        pixel[2] = r;
-                        pixel[3] = 255;
-                    }
-
-                    Some(frame_data)
-                }))
+                .append(
+                    FrameFormatAdder::builder()
+                        .buffer_key(BufferType::RawFrameBuffer)
+                        .format(format)
+                        .build(),
+                )
+                // The renderer expects RGBA frames
+                .append(
+                    PixelFormatConverter::builder()
+                        .buffer_key(BufferType::RawFrameBuffer)
+                        .target(PixelFormat::Rgba8)
+                        .build(),
+                )
+                .append(Function::new(drop_failed_frames))
                 .append(WinitRenderer::new(
                     BufferType::RawFrameBuffer,
                     args.width,
@@ -1,22 +1,51 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use log::info;
 use remotia::{
     buffers::BufferAllocator,
-    capture::scrap::ScrapFrameCapturer,
     pipeline::{component::Component, Pipeline},
-    processors::ticker::Ticker, transmission::sender::TcpFrameSender,
+    processors::{functional::Function, ticker::Ticker},
+    transmission::sender::TcpFrameSender,
+};
+use screen_mirror::{
+    drop_failed_frames,
+    geometry::FrameRescaler,
+    pattern_capturer::{Pattern, PatternCapturer, PatternResize},
+    pixel_format::{PixelFormat, PixelFormatConverter},
+    scrap_capturer::ScrapCapturer,
+    BufferType, FrameData,
 };
-use screen_mirror::{BufferType, FrameData};
 
 use tokio::net::{TcpListener, TcpStream};
 
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Source {
+    Screen,
+    Pattern,
+}
+
 #[derive(Parser, Debug)]
 struct Args {
     #[arg(short, long)]
     binding_address: String,
 
     #[arg(short, long, default_value_t = 60)]
-    framerate: u64
+    framerate: u64,
+
+    #[arg(long, value_enum, default_value_t = Source::Screen)]
+    source: Source,
+
+    #[arg(long, default_value = "smpte-bars")]
+    pattern: Pattern,
+
+    #[arg(long, default_value_t = 1280)]
+    pattern_width: u32,
+
+    #[arg(long, default_value_t = 720)]
+    pattern_height: u32,
+
+    /// Switch the pattern to this WIDTHxHEIGHT and back every FRAMES frames, as WIDTHxHEIGHT@FRAMES
+    #[arg(long)]
+    pattern_resize: Option<PatternResize>,
 }
 
 async fn establish_connection(address: &str) -> TcpStream {
@@ -32,23 +61,76 @@ async fn main() {
 
     let args = Args::parse();
 
-    let mut capturer = ScrapFrameCapturer::new_from_primary(BufferType::RawFrameBuffer);
+    let component = Component::new().append(Ticker::new(1000 / args.framerate));
+    // The client renders frames of a fixed size, that of the first ones
+    let (component, width, height) = match args.source {
+        Source::Screen => {
+            // Scrap captures BGRA frames, whose rows may be padded
+            let mut capturer = ScrapCapturer::builder()
+                .buffer_key(BufferType::RawFrameBuffer)
+                .build();
+            let format = capturer
+                .open()
+                .expect("Couldn't capture the primary display");
 
-    info!("Streaming at {}x{}", capturer.width(), capturer.height());
+            info!("Streaming at {}x{}", format.width, format.height);
 
-    let socket = establish_connection(&args.binding_address).await;
+            let component = component
+                .append(BufferAllocator::new(
+                    BufferType::RawFrameBuffer,
+                    format.buffer_size(),
+                ))
+                .append(capturer)
+                .append(
+                    PixelFormatConverter::builder()
+                        .buffer_key(BufferType::RawFrameBuffer)
+                        .target(PixelFormat::Bgra8)
+                        .build(),
+                );
 
-    let handles = Pipeline::<FrameData>::new()
-        .link(
-            Component::new()
-                .append(Ticker::new(1000 / args.framerate))
+            (component, format.width, format.height)
+        }
+        Source::Pattern => {
+            // Match the Scrap output, which is what the client expects by default
+            let capturer = PatternCapturer::builder()
+                .buffer_key(BufferType::RawFrameBuffer)
+                .pattern(args.pattern)
+                .pixel_format(PixelFormat::Bgra8)
+                .width(args.pattern_width)
+                .height(args.pattern_height)
+                .maybe_resize(args.pattern_resize)
+                .build();
+
+            info!(
+                "Streaming a test pattern at {}x{}",
+                args.pattern_width, args.pattern_height
+            );
+
+            let component = component
                 .append(BufferAllocator::new(
                     BufferType::RawFrameBuffer,
                     capturer.buffer_size(),
                 ))
-                .append(capturer)
-                .append(TcpFrameSender::new(BufferType::RawFrameBuffer, socket)),
+                .append(capturer);
+
+            (component, args.pattern_width, args.pattern_height)
+        }
+    };
+
+    let component = component
+        .append(
+            FrameRescaler::builder()
+                .buffer_key(BufferType::RawFrameBuffer)
+                .width(width)
+                .height(height)
+                .build(),
         )
+        .append(Function::new(drop_failed_frames));
+
+    let socket = establish_connection(&args.binding_address).await;
+
+    let handles = Pipeline::<FrameData>::new()
+        .link(component.append(TcpFrameSender::new(BufferType::RawFrameBuffer, socket)))
         .run();
 
     for handle in handles {
@@ -0,0 +1,230 @@
+use async_trait::async_trait;
+use bon::Builder;
+use remotia::{
+    buffers::BytesMut,
+    traits::{BorrowMutFrameProperties, FrameError, FrameProcessor, FrameProperties},
+};
+
+use crate::{pixel_format::FrameFormat, Error};
+
+/// Set by the capturers on the first frame whose format differs from the previous frame's,
+/// e.g. after the display resolution changed or a monitor was plugged in or out.
+/// Processors sized after the former format adapt to the new one when they see it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GeometryChange {
+    pub previous: FrameFormat,
+    pub current: FrameFormat,
+}
+
+impl GeometryChange {
+    /// Whether the frames changed size, and not only stride or pixel format
+    pub fn is_resize(&self) -> bool {
+        (self.previous.width, self.previous.height) != (self.current.width, self.current.height)
+    }
+}
+
+/// Follows the format of the frames written to a buffer, to tell when it changes.
+/// The first frame is compared with the format announced for the buffer, if any.
+#[derive(Clone, Debug, Default)]
+pub struct GeometryTracker {
+    last: Option<FrameFormat>,
+
+    /// Format the processors were sized after before the first frame
+    announced: Option<FrameFormat>,
+}
+
+impl GeometryTracker {
+    /// Records the format announced before the first frame, e.g. to size the buffers,
+    /// so that a first frame of another format is reported as a change
+    pub fn announce(&mut self, format: FrameFormat) {
+        if self.last.is_none() {
+            self.announced = Some(format);
+        }
+    }
+
+    /// Records the format of a new frame, returning the change from the previous one if any
+    pub fn observe(&mut self, format: FrameFormat) -> Option<GeometryChange> {
+        let previous = self
+            .last
+            .replace(format)
+            .or_else(|| self.announced.take())?;
+        if previous == format {
+            return None;
+        }
+
+        log::info!(
+            "Capture geometry changed from {}x{} to {}x{}",
+            previous.width,
+            previous.height,
+            format.width,
+            format.height
+        );
+
+        Some(GeometryChange {
+            previous,
+            current: format,
+        })
+    }
+
+    /// Declares the format of a buffer, along with the change from the previous frame if any
+    pub fn declare<K, F>(&mut self, dto: &mut F, buffer_key: K, format: FrameFormat)
+    where
+        K: Copy,
+        F: FrameProperties<K, FrameFormat> + FrameProperties<K, GeometryChange>,
+    {
+        dto.set(buffer_key, format);
+        if let Some(change) = self.observe(format) {
+            dto.set(buffer_key, change);
+        }
+    }
+}
+
+/// Nearest-neighbour mapping of frames onto a frame of another size, keeping their aspect ratio.
+/// They are centered, the borders being filled with black.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Letterbox {
+    source: (u32, u32),
+    target: (u32, u32),
+
+    /// Source column of each target column, `None` within the borders
+    columns: Vec<Option<usize>>,
+    rows: Vec<Option<usize>>,
+}
+
+impl Letterbox {
+    pub fn new(source_width: u32, source_height: u32, width: u32, height: u32) -> Self {
+        let scale = f64::min(
+            width as f64 / source_width.max(1) as f64,
+            height as f64 / source_height.max(1) as f64,
+        );
+        let scaled =
+            |side: u32, target: u32| ((side as f64 * scale).round() as u32).clamp(1, target.max(1));
+
+        Self {
+            source: (source_width, source_height),
+            target: (width, height),
+            columns: map_axis(source_width, width, scaled(source_width, width)),
+            rows: map_axis(source_height, height, scaled(source_height, height)),
+        }
+    }
+
+    /// Whether this letterbox maps frames of the given size to frames of the target size
+    pub fn maps(&self, source_width: u32, source_height: u32, width: u32, height: u32) -> bool {
+        self.source == (source_width, source_height) && self.target == (width, height)
+    }
+
+    /// Maps a frame which fits the format, returning a packed frame in the same pixel format
+    pub fn apply(&self, format: FrameFormat, buffer: &[u8]) -> Vec<u8> {
+        let pixel_format = format.pixel_format;
+        let bpp = pixel_format.bytes_per_pixel();
+        let (width, height) = self.target;
+
+        let mut output = vec![0; width as usize * height as usize * bpp];
+        for (y, output_row) in output.chunks_exact_mut(width as usize * bpp).enumerate() {
+            let source_row = self.rows[y].map(|row| &buffer[row * format.stride..]);
+
+            for (x, pixel) in output_row.chunks_exact_mut(bpp).enumerate() {
+                match (source_row, self.columns[x]) {
+                    (Some(source_row), Some(column)) => {
+                        pixel.copy_from_slice(&source_row[column * bpp..(column + 1) * bpp])
+                    }
+                    _ => pixel_format.write(pixel, [0, 0, 0]),
+                }
+            }
+        }
+
+        output
+    }
+}
+
+/// Source index of each position along an axis, the scaled source being centered on it
+fn map_axis(source: u32, target: u32, scaled: u32) -> Vec<Option<usize>> {
+    let offset = target.saturating_sub(scaled) / 2;
+
+    (0..target)
+        .map(|position| {
+            let position = position
+                .checked_sub(offset)
+                .filter(|position| *position < scaled)?;
+            Some((position as u64 * source as u64 / scaled as u64) as usize)
+        })
+        .collect()
+}
+
+/// Keeps the frames of a buffer at a fixed size, letterboxing those captured at another size,
+/// for the processors which cannot follow a geometry change such as encoders or fixed-size renderers
+#[derive(Builder)]
+pub struct FrameRescaler<K> {
+    #[builder(skip)]
+    letterbox: Option<Letterbox>,
+
+    buffer_key: K,
+
+    width: u32,
+    height: u32,
+}
+
+#[async_trait]
+impl<K, F> FrameProcessor<F> for FrameRescaler<K>
+where
+    F: Send + 'static,
+    K: Send + Copy,
+    F: BorrowMutFrameProperties<K, BytesMut> + FrameProperties<K, FrameFormat> + FrameError<Error>,
+{
+    async fn process(&mut self, mut dto: F) -> Option<F> {
+        let Some(format) = dto.get(&self.buffer_key) else {
+            log::warn!("No format declared for the buffer");
+            dto.report_error(Error::MissingFormat);
+            return Some(dto);
+        };
+
+        if (format.width, format.height) == (self.width, self.height) {
+            return Some(dto);
+        }
+
+        let Some(buffer) = dto.get_mut_ref(&self.buffer_key) else {
+            dto.report_error(Error::MissingBuffer);
+            return Some(dto);
+        };
+
+        if !format.fits(buffer) {
+            log::warn!(
+                "Buffer of {} bytes does not match {:?}",
+                buffer.len(),
+                format
+            );
+            dto.report_error(Error::SizeMismatch);
+            return Some(dto);
+        }
+
+        let letterbox = match self.letterbox.take() {
+            Some(letterbox)
+                if letterbox.maps(format.width, format.height, self.width, self.height) =>
+            {
+                letterbox
+            }
+            _ => {
+                log::info!(
+                    "Scaling {}x{} frames to {}x{}",
+                    format.width,
+                    format.height,
+                    self.width,
+                    self.height
+                );
+                Letterbox::new(format.width, format.height, self.width, self.height)
+            }
+        };
+
+        let scaled = letterbox.apply(format, buffer);
+        self.letterbox = Some(letterbox);
+
+        buffer.clear();
+        buffer.extend_from_slice(&scaled);
+
+        dto.set(
+            self.buffer_key,
+            FrameFormat::packed(format.pixel_format, self.width, self.height),
+        );
+        Some(dto)
+    }
+}
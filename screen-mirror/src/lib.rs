@@ -1,14 +1,34 @@
+pub mod geometry;
+pub mod pattern_capturer;
+pub mod pixel_format;
+pub mod scrap_capturer;
+
 use bytes::BytesMut;
-use remotia::traits::{BorrowFrameProperties, BorrowMutFrameProperties, PullableFrameProperties};
+use geometry::GeometryChange;
+use pixel_format::FrameFormat;
+use remotia::traits::{
+    BorrowFrameProperties, BorrowMutFrameProperties, FrameError, FrameProperties,
+    PullableFrameProperties,
+};
 
 #[derive(Copy, Clone, Debug)]
 pub enum BufferType {
     RawFrameBuffer,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    MissingBuffer,
+    MissingFormat,
+    SizeMismatch,
+}
+
 #[derive(Default, Debug)]
 pub struct FrameData {
     raw_frame_buffer: BytesMut,
+    raw_frame_format: Option<FrameFormat>,
+    raw_frame_geometry_change: Option<GeometryChange>,
+    error: Option<Error>,
 }
 
 impl BorrowMutFrameProperties<BufferType, BytesMut> for FrameData {
@@ -40,3 +60,53 @@ impl PullableFrameProperties<BufferType, BytesMut> for FrameData {
         }
     }
 }
+
+impl FrameProperties<BufferType, FrameFormat> for FrameData {
+    fn set(&mut self, key: BufferType, value: FrameFormat) {
+        match key {
+            BufferType::RawFrameBuffer => self.raw_frame_format = Some(value),
+        }
+    }
+
+    fn get(&self, key: &BufferType) -> Option<FrameFormat> {
+        match key {
+            BufferType::RawFrameBuffer => self.raw_frame_format,
+        }
+    }
+}
+
+impl FrameProperties<BufferType, GeometryChange> for FrameData {
+    fn set(&mut self, key: BufferType, value: GeometryChange) {
+        match key {
+            BufferType::RawFrameBuffer => self.raw_frame_geometry_change = Some(value),
+        }
+    }
+
+    fn get(&self, key: &BufferType) -> Option<GeometryChange> {
+        match key {
+            BufferType::RawFrameBuffer => self.raw_frame_geometry_change,
+        }
+    }
+}
+
+impl FrameError<Error> for FrameData {
+    fn report_error(&mut self, error: Error) {
+        self.error = Some(error);
+    }
+
+    fn get_error(&self) -> Option<Error> {
+        self.error
+    }
+}
+
+/// Drops the frames an error was reported for, before they are sent or rendered.
+/// Their buffers are allocated per frame, so there is nothing to give back.
+pub fn drop_failed_frames(frame_data: FrameData) -> Option<FrameData> {
+    match frame_data.get_error() {
+        Some(error) => {
+            log::warn!("Dropped frame: {:?}", error);
+            None
+        }
+        None => Some(frame_data),
+    }
+}
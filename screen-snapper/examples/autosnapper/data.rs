@@ -1,22 +1,34 @@
 use remotia::{
     buffers::BytesMut,
-    traits::{BorrowMutFrameProperties, PullableFrameProperties},
+    traits::{BorrowMutFrameProperties, FrameError, FrameProperties, PullableFrameProperties},
+};
+use screen_snapper::{
+    change_detector::ChangeScore, error::Error, geometry::GeometryChange, pixel_format::FrameFormat,
 };
 
 #[derive(Default, Debug)]
 pub struct RecorderData {
     pub(crate) screen_buffer: Option<BytesMut>,
+    pub(crate) screen_format: Option<FrameFormat>,
+    pub(crate) screen_geometry_change: Option<GeometryChange>,
+    pub(crate) thumbnail_buffer: Option<BytesMut>,
+    pub(crate) thumbnail_format: Option<FrameFormat>,
+    pub(crate) thumbnail_geometry_change: Option<GeometryChange>,
+    pub(crate) change_score: Option<ChangeScore>,
+    pub(crate) error: Option<Error>,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Buffers {
     CapturedScreenBuffer,
+    ThumbnailBuffer,
 }
 
 impl BorrowMutFrameProperties<Buffers, BytesMut> for RecorderData {
     fn get_mut_ref(&mut self, key: &Buffers) -> Option<&mut BytesMut> {
         match key {
             Buffers::CapturedScreenBuffer => self.screen_buffer.as_mut(),
+            Buffers::ThumbnailBuffer => self.thumbnail_buffer.as_mut(),
         }
     }
 }
@@ -25,12 +37,72 @@ impl PullableFrameProperties<Buffers, BytesMut> for RecorderData {
     fn push(&mut self, key: Buffers, value: BytesMut) {
         match key {
             Buffers::CapturedScreenBuffer => self.screen_buffer.replace(value),
+            Buffers::ThumbnailBuffer => self.thumbnail_buffer.replace(value),
         };
     }
 
     fn pull(&mut self, key: &Buffers) -> Option<BytesMut> {
         match key {
             Buffers::CapturedScreenBuffer => self.screen_buffer.take(),
+            Buffers::ThumbnailBuffer => self.thumbnail_buffer.take(),
         }
     }
 }
+
+impl FrameProperties<Buffers, FrameFormat> for RecorderData {
+    fn set(&mut self, key: Buffers, value: FrameFormat) {
+        match key {
+            Buffers::CapturedScreenBuffer => self.screen_format = Some(value),
+            Buffers::ThumbnailBuffer => self.thumbnail_format = Some(value),
+        }
+    }
+
+    fn get(&self, key: &Buffers) -> Option<FrameFormat> {
+        match key {
+            Buffers::CapturedScreenBuffer => self.screen_format,
+            Buffers::ThumbnailBuffer => self.thumbnail_format,
+        }
+    }
+}
+
+impl FrameProperties<Buffers, GeometryChange> for RecorderData {
+    fn set(&mut self, key: Buffers, value: GeometryChange) {
+        match key {
+            Buffers::CapturedScreenBuffer => self.screen_geometry_change = Some(value),
+            Buffers::ThumbnailBuffer => self.thumbnail_geometry_change = Some(value),
+        }
+    }
+
+    fn get(&self, key: &Buffers) -> Option<GeometryChange> {
+        match key {
+            Buffers::CapturedScreenBuffer => self.screen_geometry_change,
+            Buffers::ThumbnailBuffer => self.thumbnail_geometry_change,
+        }
+    }
+}
+
+impl FrameProperties<Buffers, ChangeScore> for RecorderData {
+    fn set(&mut self, key: Buffers, value: ChangeScore) {
+        match key {
+            Buffers::CapturedScreenBuffer => self.change_score = Some(value),
+            Buffers::ThumbnailBuffer => {}
+        }
+    }
+
+    fn get(&self, key: &Buffers) -> Option<ChangeScore> {
+        match key {
+            Buffers::CapturedScreenBuffer => self.change_score,
+            Buffers::ThumbnailBuffer => None,
+        }
+    }
+}
+
+impl FrameError<Error> for RecorderData {
+    fn report_error(&mut self, error: Error) {
+        self.error = Some(error);
+    }
+
+    fn get_error(&self) -> Option<Error> {
+        self.error
+    }
+}
@@ -1,57 +1,905 @@
+use std::{env, path::PathBuf, sync::Arc, time::Duration};
+
+use clap::{CommandFactory, Parser, ValueEnum, error::ErrorKind};
 use data::{Buffers, RecorderData};
+use limit::SnapshotLimit;
 use remotia::{
-    buffers::BufferAllocator,
-    pipeline::{Pipeline, component::Component},
-    processors::ticker::Ticker,
+    buffers::pool_registry::PoolRegistry,
+    pipeline::{Pipeline, component::Component, registry::PipelineRegistry},
+    processors::{error_switch::OnErrorSwitch, functional::Function, ticker::Ticker},
+    register,
+    traits::{FrameError, FrameProperties},
 };
+#[cfg(unix)]
+use screen_snapper::control::ControlServer;
 use screen_snapper::{
-    png_saver::PNGBufferSaver,
-    xcap_capturer::{XCapCapturer, xcap_utils},
+    catalog::Catalog,
+    change_detector::{ChangeDetector, ChangeMetric, ChangeScore, DEFAULT_CHANGE_THRESHOLD},
+    control::{Controller, Settle, Trigger},
+    encryption::EncryptionKey,
+    error::Error,
+    file_name::{CollisionPolicy, DEFAULT_FILE_NAME, FileNameTemplate},
+    image_saver::{ImageBufferSaver, ImageFormat},
+    layout::{MonitorGeometry, MultiMonitorMode},
+    metadata::CaptureSource,
+    monitor_selector::MonitorSelector,
+    overlay::{Color, DEFAULT_OVERLAY_SCALE, OverlayPosition, OverlayText, TextOverlay},
+    pattern_capturer::{Pattern, PatternCapturer, PatternResize},
+    pixel_format::{FrameFormat, PixelFormat},
+    redaction::{AreaLocator, RedactionArea, RedactionStyle, Redactor},
+    region::Region,
+    resize::{ResizeFilter, ResizeMode, Resizer, Size},
+    retention::{RetentionEnforcer, RetentionPolicy},
+    s3_storage::{DEFAULT_S3_REGION, DEFAULT_UPLOAD_ATTEMPTS, RetryPolicy, S3Credentials, S3Sink},
+    storage::{FileSystemSink, StorageSink},
+    timelapse::{DEFAULT_TIMELAPSE_FRAME_RATE, TimelapseFormat, TimelapseWriter},
+    worker_pool::{DEFAULT_QUEUE_SIZE, DEFAULT_WORKERS, QueueFullPolicy, WorkerPool},
+    xcap_capturer::XCapCapturer,
+    xcap_multi_capturer::XCapMultiCapturer,
+    xcap_window_capturer::{ResizePolicy, WindowSelector, XCapWindowCapturer, XCapWindowLocator},
 };
 mod data;
+mod limit;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Source {
+    Screen,
+    /// All monitors, stitched into a single frame
+    Desktop,
+    Window,
+    Pattern,
+}
+
+#[derive(Parser, Debug)]
+struct Args {
+    #[arg(long, value_enum, default_value_t = Source::Screen)]
+    source: Source,
+
+    /// Monitor to capture: primary, a name, index:<n> or point:<x>,<y>
+    #[arg(long, default_value = "primary")]
+    monitor: MonitorSelector,
+
+    /// Only capture this WIDTHxHEIGHT+X+Y rectangle
+    #[arg(long)]
+    region: Option<Region>,
+
+    /// Window to capture, as title:<substring>, app:<name> or id:<window id>
+    #[arg(long, required_if_eq("source", "window"))]
+    window: Option<WindowSelector>,
+
+    /// What to do when the window is resized: reject, scale or follow
+    #[arg(long, default_value = "reject")]
+    on_resize: ResizePolicy,
+
+    #[arg(long, default_value = "smpte-bars")]
+    pattern: Pattern,
+
+    #[arg(long, default_value_t = 1280)]
+    pattern_width: u32,
+
+    #[arg(long, default_value_t = 720)]
+    pattern_height: u32,
+
+    /// Pixel format of the pattern frames: rgb, bgr, rgba or bgra
+    #[arg(long, default_value = "rgb")]
+    pattern_pixel_format: PixelFormat,
+
+    /// Switch the pattern to this WIDTHxHEIGHT and back every FRAMES frames, as WIDTHxHEIGHT@FRAMES
+    #[arg(long)]
+    pattern_resize: Option<PatternResize>,
+
+    #[arg(long, default_value = "png")]
+    format: ImageFormat,
+
+    /// Hide this WIDTHxHEIGHT+X+Y rectangle of the frames, can be repeated
+    #[arg(long)]
+    redact: Vec<RedactionArea>,
+
+    /// Hide the windows matching this title:, app: or id: selector, can be repeated
+    #[arg(long)]
+    redact_window: Vec<WindowSelector>,
+
+    /// How to hide the redacted areas: black, pixelate[:SIZE] or blur[:RADIUS]
+    #[arg(long, default_value = "black")]
+    redact_style: RedactionStyle,
+
+    /// Burn this text into the saved snapshots. Placeholders: {time} or {time:FORMAT}
+    /// (strftime-style), {hostname} and {monitor}, \n starting a new line
+    #[arg(long)]
+    overlay: Option<OverlayText>,
+
+    /// Where to draw the overlay: top-left, top-right, bottom-left, bottom-right or X,Y
+    #[arg(long, default_value = "bottom-right")]
+    overlay_position: OverlayPosition,
+
+    /// Overlay text color, as a name or #RRGGBB
+    #[arg(long, default_value = "white")]
+    overlay_color: Color,
+
+    /// Color of the box behind the overlay text, none by default
+    #[arg(long)]
+    overlay_background: Option<Color>,
+
+    /// Size of the overlay font pixels, in frame pixels
+    #[arg(long, default_value_t = DEFAULT_OVERLAY_SCALE)]
+    overlay_scale: u32,
+
+    /// Milliseconds between two snapshots
+    #[arg(long, default_value_t = 1000)]
+    interval: u64,
+
+    /// Run as a daemon taking commands on this Unix domain socket, see the snapperctl example
+    #[cfg(unix)]
+    #[arg(long)]
+    control_socket: Option<PathBuf>,
+
+    /// Directory the snapshots are saved to, created if missing
+    #[arg(long, default_value = "./screenshots/")]
+    output_dir: PathBuf,
+
+    /// Upload the snapshots to this bucket of an S3-compatible service instead of the output
+    /// directory, signing the requests with AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY if set
+    #[arg(long)]
+    s3_bucket: Option<String>,
+
+    /// URL of the S3-compatible service
+    #[arg(long, default_value = "http://127.0.0.1:9000")]
+    s3_endpoint: String,
+
+    #[arg(long, default_value = DEFAULT_S3_REGION)]
+    s3_region: String,
+
+    /// Prepended to the names of the uploaded objects, e.g. snapshots/
+    #[arg(long, default_value = "")]
+    s3_prefix: String,
+
+    /// Attempts at most per upload, failed ones being retried with a growing delay
+    #[arg(long, default_value_t = DEFAULT_UPLOAD_ATTEMPTS)]
+    upload_attempts: u32,
+
+    /// File name, without extension. Placeholders: {id} or {id:N} (zero-padded),
+    /// {time} or {time:FORMAT} (strftime-style), {monitor}, {hostname}, {width} and {height}
+    #[arg(long, default_value = DEFAULT_FILE_NAME)]
+    file_name: FileNameTemplate,
+
+    /// What to do when a file already exists: skip, overwrite or suffix
+    #[arg(long, default_value = "suffix")]
+    on_collision: CollisionPolicy,
+
+    /// Continue numbering from the highest existing snapshot
+    #[arg(long)]
+    resume: bool,
+
+    /// Only keep frames which changed since the last kept one: exact, pixels or phash
+    #[arg(long)]
+    change_metric: Option<ChangeMetric>,
+
+    /// Minimum change score, from 0 to 1, of the kept frames
+    #[arg(long, default_value_t = DEFAULT_CHANGE_THRESHOLD)]
+    change_threshold: f64,
+
+    /// Stop after taking this many snapshots
+    #[arg(long)]
+    max_count: Option<usize>,
+
+    /// Keep at most this many snapshots, and as many thumbnails. The limits only apply
+    /// to the snapshots listed in the index, which they enable as with --index.
+    #[arg(long)]
+    keep_count: Option<usize>,
+
+    /// Keep at most this many bytes of snapshots, and of thumbnails
+    #[arg(long)]
+    keep_bytes: Option<u64>,
+
+    /// Delete the snapshots and thumbnails older than this, e.g. 12h or 7days
+    #[arg(long, value_parser = humantime::parse_duration)]
+    keep_age: Option<Duration>,
+
+    /// Enforce the retention limits every this many milliseconds, instead of after each save
+    #[arg(long)]
+    retention_interval: Option<u64>,
+
+    /// Threads encoding and writing the snapshots, 0 doing it within the pipeline
+    #[arg(long, default_value_t = DEFAULT_WORKERS)]
+    save_workers: usize,
+
+    /// Snapshots waiting for a saving thread at most
+    #[arg(long, default_value_t = DEFAULT_QUEUE_SIZE)]
+    save_queue: usize,
+
+    /// What to do when the saving queue is full: block or drop-oldest
+    #[arg(long, default_value = "block")]
+    on_queue_full: QueueFullPolicy,
+
+    /// Do not store the capture metadata in the PNG and JPEG files
+    #[arg(long)]
+    no_metadata: bool,
+
+    /// Append the capture metadata of each snapshot to index.jsonl in the output directory,
+    /// or to parts under index.jsonl.parts/ in the bucket, merged into it by the keep-* limits
+    #[arg(long)]
+    index: bool,
+
+    /// Record each snapshot in this SQLite catalog, created if missing, see the snapcatalog example
+    #[arg(long)]
+    catalog: Option<PathBuf>,
+
+    /// Encrypt the snapshots and thumbnails with the key of this file, see the snapcrypt example.
+    /// Keep the keys replaced by newer ones, as they decrypt the snapshots taken before.
+    #[arg(long, conflicts_with_all = ["encrypt_passphrase_file", "timelapse"])]
+    encrypt_key: Option<PathBuf>,
+
+    /// Encrypt the snapshots and thumbnails with a key derived from the passphrase in this file
+    #[arg(long, conflicts_with = "timelapse")]
+    encrypt_passphrase_file: Option<PathBuf>,
+
+    /// Also append each snapshot to this timelapse file, continued if it exists
+    #[arg(long)]
+    timelapse: Option<PathBuf>,
+
+    /// Replace the --timelapse file if it exists, instead of continuing it
+    #[arg(long, requires = "timelapse")]
+    timelapse_overwrite: bool,
+
+    /// Timelapse format: gif, apng or y4m, guessed from the file extension by default
+    #[arg(long)]
+    timelapse_format: Option<TimelapseFormat>,
+
+    /// Timelapse playback rate, in frames per second
+    #[arg(long, default_value_t = DEFAULT_TIMELAPSE_FRAME_RATE)]
+    timelapse_rate: u32,
+
+    /// Divide the width and height of the timelapse frames by this factor
+    #[arg(long, default_value_t = 1)]
+    timelapse_downscale: u32,
+
+    /// Also save a copy of each snapshot scaled to this WIDTHxHEIGHT
+    #[arg(long)]
+    thumbnail: Option<Size>,
+
+    /// How the thumbnails match their size: fit, fill or exact
+    #[arg(long, default_value = "fit")]
+    thumbnail_mode: ResizeMode,
+
+    /// Thumbnail scaling filter: nearest, bilinear or lanczos
+    #[arg(long, default_value = "bilinear")]
+    thumbnail_filter: ResizeFilter,
+
+    /// Directory the thumbnails are saved to, <OUTPUT_DIR>/thumbnails by default
+    #[arg(long)]
+    thumbnail_dir: Option<PathBuf>,
+}
+
+impl Args {
+    fn retention_policy(&self) -> RetentionPolicy {
+        RetentionPolicy {
+            max_count: self.keep_count,
+            max_bytes: self.keep_bytes,
+            max_age: self.keep_age,
+        }
+    }
+
+    /// Whether the savers append to an index, which the retention limits rely on
+    fn index(&self) -> bool {
+        self.index || !self.retention_policy().is_unlimited()
+    }
+
+    /// Enforcer of the keep-* limits on the snapshots, or their thumbnails, if any limit is set.
+    /// Deleted snapshots are removed from the catalog.
+    fn retention_enforcer(
+        &self,
+        thumbnails: bool,
+        catalog: Option<Arc<Catalog>>,
+    ) -> Option<RetentionEnforcer> {
+        let policy = self.retention_policy();
+        if policy.is_unlimited() || (thumbnails && self.thumbnail.is_none()) {
+            return None;
+        }
+
+        Some(
+            RetentionEnforcer::builder()
+                .sink(self.sink(thumbnails))
+                .policy(policy)
+                .maybe_catalog(catalog.filter(|_| !thumbnails))
+                .build(),
+        )
+    }
+
+    /// Redactor of the --redact areas and windows, if any, the windows being located
+    /// relative to the origin of the captured surface
+    fn redactor(&self, origin: Option<(i32, i32)>) -> Option<Redactor<Buffers>> {
+        if self.redact.is_empty() && self.redact_window.is_empty() {
+            return None;
+        }
+
+        let mut locators: Vec<Box<dyn AreaLocator>> = Vec::new();
+        if !self.redact_window.is_empty() {
+            // Screen and desktop captures, as checked by `validate`, are located once opened
+            let origin = origin.expect("Unable to locate the captured screen");
+            locators.push(Box::new(XCapWindowLocator::new(
+                self.redact_window.clone(),
+                origin,
+            )));
+        }
+
+        Some(
+            Redactor::builder()
+                .buffer_key(Buffers::CapturedScreenBuffer)
+                .areas(self.redact.clone())
+                .locators(locators)
+                .style(self.redact_style)
+                .build(),
+        )
+    }
+
+    /// Overlay of the --overlay text, if any
+    fn text_overlay(&self, monitor: Option<String>) -> Option<TextOverlay<Buffers>> {
+        let text = self.overlay.clone()?;
+
+        Some(
+            TextOverlay::builder()
+                .buffer_key(Buffers::CapturedScreenBuffer)
+                .text(text)
+                .maybe_monitor(monitor)
+                .position(self.overlay_position)
+                .color(self.overlay_color)
+                .maybe_background(self.overlay_background)
+                .scale(self.overlay_scale)
+                .build(),
+        )
+    }
+
+    /// Format of the --timelapse file, if any and known
+    fn timelapse_format(&self) -> Option<TimelapseFormat> {
+        let path = self.timelapse.as_ref()?;
+
+        self.timelapse_format.or_else(|| {
+            path.extension()
+                .and_then(|extension| extension.to_str())
+                .and_then(|extension| extension.parse().ok())
+        })
+    }
+
+    /// Checks the arguments clap cannot check on its own
+    fn validate(&self) -> Result<(), clap::Error> {
+        if !self.redact_window.is_empty() && matches!(self.source, Source::Window | Source::Pattern)
+        {
+            return Err(Self::command().error(
+                ErrorKind::ArgumentConflict,
+                "--redact-window only applies to the screen and desktop sources",
+            ));
+        }
+
+        if self.timelapse.is_some() && self.timelapse_format().is_none() {
+            return Err(Self::command().error(
+                ErrorKind::InvalidValue,
+                "unable to guess the format of --timelapse from its extension, use --timelapse-format",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Resizer of the snapshots into --thumbnail copies, if any
+    fn thumbnail_resizer(&self) -> Option<Resizer<Buffers>> {
+        let size = self.thumbnail?;
+
+        Some(
+            Resizer::builder()
+                .input_key(Buffers::CapturedScreenBuffer)
+                .output_key(Buffers::ThumbnailBuffer)
+                .size(size)
+                .mode(self.thumbnail_mode)
+                .filter(self.thumbnail_filter)
+                .build(),
+        )
+    }
+
+    fn thumbnail_dir(&self) -> PathBuf {
+        self.thumbnail_dir
+            .clone()
+            .unwrap_or_else(|| self.output_dir.join("thumbnails"))
+    }
+
+    /// Server of the --control-socket, if any
+    #[cfg(unix)]
+    fn control_server(&self) -> Option<ControlServer> {
+        let path = self.control_socket.as_ref()?;
+
+        Some(
+            ControlServer::bind(path, Controller::new(self.interval))
+                .expect("Unable to listen on the control socket"),
+        )
+    }
+
+    /// Where the snapshots, or their thumbnails, are stored
+    fn sink(&self, thumbnails: bool) -> Arc<dyn StorageSink> {
+        let Some(bucket) = &self.s3_bucket else {
+            let directory = if thumbnails {
+                self.thumbnail_dir()
+            } else {
+                self.output_dir.clone()
+            };
+            return Arc::new(FileSystemSink::new(directory));
+        };
+
+        let prefix = if thumbnails {
+            format!("{}thumbnails/", self.s3_prefix)
+        } else {
+            self.s3_prefix.clone()
+        };
+
+        let credentials = match (
+            env::var("AWS_ACCESS_KEY_ID"),
+            env::var("AWS_SECRET_ACCESS_KEY"),
+        ) {
+            (Ok(access_key), Ok(secret_key)) => Some(S3Credentials {
+                access_key,
+                secret_key,
+            }),
+            _ => None,
+        };
+
+        Arc::new(
+            S3Sink::builder()
+                .endpoint(&self.s3_endpoint)
+                .bucket(bucket)
+                .prefix(prefix)
+                .region(&self.s3_region)
+                .maybe_credentials(credentials)
+                .retry(RetryPolicy {
+                    attempts: self.upload_attempts,
+                    ..RetryPolicy::default()
+                })
+                .build(),
+        )
+    }
+
+    /// Key of the --encrypt-* options, if any
+    fn encryption_key(&self) -> Option<Arc<EncryptionKey>> {
+        let key = if let Some(path) = &self.encrypt_key {
+            EncryptionKey::from_key_file(path).expect("Unable to read the encryption key")
+        } else {
+            let path = self.encrypt_passphrase_file.as_ref()?;
+            let passphrase =
+                std::fs::read_to_string(path).expect("Unable to read the passphrase file");
+            let passphrase = passphrase.trim_end_matches(['\r', '\n']);
+            assert!(!passphrase.is_empty(), "The passphrase file is empty");
+
+            EncryptionKey::from_passphrase(passphrase).expect("Unable to derive the encryption key")
+        };
+
+        log::info!("Encrypting the snapshots with key {}", key.id());
+        Some(Arc::new(key))
+    }
+
+    /// Catalog of the --catalog option, if any
+    fn catalog(&self) -> Option<Arc<Catalog>> {
+        let path = self.catalog.as_ref()?;
+        Some(Arc::new(
+            Catalog::open(path).expect("Unable to open the catalog"),
+        ))
+    }
+
+    fn save_workers(&self) -> Option<WorkerPool> {
+        (self.save_workers > 0)
+            .then(|| WorkerPool::new(self.save_workers, self.save_queue, self.on_queue_full))
+    }
+}
+
+#[derive(PartialEq, Eq, Hash)]
+enum Pipelines {
+    Main,
+    Error,
+    Retention,
+    ThumbnailRetention,
+}
+
+enum Capturer {
+    Screen(XCapCapturer<Buffers>),
+    Desktop(XCapMultiCapturer<Buffers>),
+    Window(XCapWindowCapturer<Buffers>),
+    Pattern(PatternCapturer<Buffers>),
+}
+
+impl Capturer {
+    fn new(args: &Args) -> Result<Self, Error> {
+        match args.source {
+            Source::Screen => {
+                let mut capturer = XCapCapturer::builder()
+                    .buffer_key(Buffers::CapturedScreenBuffer)
+                    .monitor(args.monitor.clone())
+                    .maybe_region(args.region)
+                    .build();
+                capturer.open()?;
+                Ok(Self::Screen(capturer))
+            }
+            Source::Desktop => {
+                let mut capturer = XCapMultiCapturer::builder()
+                    .mode(MultiMonitorMode::Stitched(Buffers::CapturedScreenBuffer))
+                    .build();
+                capturer.open()?;
+                log::info!("Capturing monitors {:?}", capturer.layout().monitors);
+                Ok(Self::Desktop(capturer))
+            }
+            Source::Window => {
+                let mut capturer = XCapWindowCapturer::builder()
+                    .buffer_key(Buffers::CapturedScreenBuffer)
+                    .window(args.window.clone().expect("A window selector is required"))
+                    .on_resize(args.on_resize)
+                    .build();
+                capturer.open()?;
+                Ok(Self::Window(capturer))
+            }
+            Source::Pattern => Ok(Self::Pattern(
+                PatternCapturer::builder()
+                    .buffer_key(Buffers::CapturedScreenBuffer)
+                    .pattern(args.pattern)
+                    .pixel_format(args.pattern_pixel_format)
+                    .width(args.pattern_width)
+                    .height(args.pattern_height)
+                    .maybe_resize(args.pattern_resize)
+                    .build(),
+            )),
+        }
+    }
+
+    fn frame_format(&self) -> Result<FrameFormat, Error> {
+        match self {
+            Self::Screen(capturer) => capturer.frame_format(),
+            Self::Desktop(capturer) => Ok(capturer.layout().canvas_format()),
+            Self::Window(capturer) => capturer.frame_format(),
+            Self::Pattern(capturer) => Ok(capturer.frame_format()),
+        }
+    }
+
+    /// Top-left corner of the captured frames in desktop coordinates
+    fn capture_origin(&self) -> Option<(i32, i32)> {
+        match self {
+            Self::Screen(capturer) => capturer.capture_origin(),
+            Self::Desktop(capturer) => Some(capturer.layout().origin()),
+            Self::Window(_) | Self::Pattern(_) => None,
+        }
+    }
+
+    fn capture_source(&self) -> CaptureSource {
+        let xcap = Some("xcap".to_string());
+
+        match self {
+            Self::Screen(capturer) => CaptureSource {
+                backend: xcap,
+                monitor: capturer.monitor_geometry(),
+            },
+            Self::Desktop(capturer) => {
+                let layout = capturer.layout();
+                let (x, y) = layout.origin();
+                let (height, width) = layout.canvas_size();
+
+                CaptureSource {
+                    backend: xcap,
+                    monitor: Some(MonitorGeometry {
+                        name: "desktop".to_string(),
+                        x,
+                        y,
+                        width,
+                        height,
+                    }),
+                }
+            }
+            Self::Window(_) => CaptureSource {
+                backend: xcap,
+                monitor: None,
+            },
+            Self::Pattern(_) => CaptureSource {
+                backend: Some("pattern".to_string()),
+                monitor: None,
+            },
+        }
+    }
+}
+
+const POOLS_SIZE: usize = 1;
 
 #[tokio::main]
 async fn main() {
     env_logger::init();
 
-    let monitor_id = 0;
-    let (height, width) = xcap_utils::display_size(monitor_id);
+    let args = Args::parse();
+    if let Err(err) = args.validate() {
+        err.exit();
+    }
+
+    let capturer = Capturer::new(&args).expect("Unable to initialise the capturer");
+    let frame_format = capturer
+        .frame_format()
+        .expect("Unable to fetch the capture format");
+
+    let capture_source = capturer.capture_source();
+
+    log::debug!("Detected capture format: {:?}", frame_format);
+
+    std::fs::create_dir_all(&args.output_dir).expect("Unable to create the output directory");
+
+    // Stops the snapper on a shutdown command, or once --max-count snapshots are taken
+    #[cfg(unix)]
+    let controller = match args.control_server() {
+        Some(server) => {
+            let controller = server.controller().clone();
+            tokio::spawn(server.run());
+            controller
+        }
+        None => Controller::new(args.interval),
+    };
+    #[cfg(not(unix))]
+    let controller = Controller::new(args.interval);
+
+    let workers = args.save_workers();
+    let catalog = args.catalog();
+
+    let mut pools = PoolRegistry::new();
+    pools
+        .register(
+            Buffers::CapturedScreenBuffer,
+            POOLS_SIZE,
+            frame_format.buffer_size(),
+        )
+        .await;
+
+    let thumbnail_format = args
+        .thumbnail_resizer()
+        .map(|resizer| resizer.output_format(frame_format));
+    if let Some(thumbnail_format) = thumbnail_format {
+        if args.s3_bucket.is_none() {
+            std::fs::create_dir_all(args.thumbnail_dir())
+                .expect("Unable to create the thumbnail directory");
+        }
 
-    log::debug!("Detected display size: {}x{}", width, height);
+        pools
+            .register(
+                Buffers::ThumbnailBuffer,
+                POOLS_SIZE,
+                thumbnail_format.buffer_size(),
+            )
+            .await;
+    }
+
+    let mut pipelines = PipelineRegistry::<RecorderData, Pipelines>::new();
+
+    let error_component = Component::new()
+        .append(Function::new(|frame_data: RecorderData| {
+            match frame_data.get_error() {
+                Some(Error::Unchanged) => log::debug!("Skipped unchanged frame"),
+                error => log::warn!("Dropped frame: {:?}", error),
+            }
+            Some(frame_data)
+        }))
+        .append(pools.get(Buffers::CapturedScreenBuffer).redeemer().soft());
+
+    let error_component = match thumbnail_format {
+        Some(_) => error_component.append(pools.get(Buffers::ThumbnailBuffer).redeemer().soft()),
+        None => error_component,
+    };
+    let error_component = error_component.append(Settle::new(controller.clone()));
+
+    register!(
+        pipelines,
+        Pipelines::Error,
+        Pipeline::<RecorderData>::singleton(error_component).feedable()
+    );
+
+    let saver = saver(
+        &args,
+        capture_source,
+        workers.clone(),
+        catalog.clone(),
+        &pools,
+        pipelines.get_mut(&Pipelines::Error),
+    );
 
-    let pipeline = Pipeline::<RecorderData>::new()
-        .link(capturer(monitor_id, height, width))
-        .link(saver(height, width));
+    // Counted once the snapshot is saved and its buffer given back
+    let saver = match args.max_count {
+        Some(max_count) => saver.append(SnapshotLimit::new(max_count, controller.clone())),
+        None => saver,
+    };
+    let saver = saver.append(Settle::new(controller.clone()));
 
-    for handle in pipeline.run() {
-        handle
-            .await
-            .expect(&format!("Error while awaiting for pipeline to finish"));
+    register!(
+        pipelines,
+        Pipelines::Main,
+        Pipeline::<RecorderData>::new()
+            .link(capturer_component(
+                capturer,
+                controller.clone(),
+                &args,
+                &pools,
+                pipelines.get_mut(&Pipelines::Error),
+            ))
+            .link(saver)
+    );
+
+    if let Some(interval) = args.retention_interval {
+        for (id, thumbnails) in [
+            (Pipelines::Retention, false),
+            (Pipelines::ThumbnailRetention, true),
+        ] {
+            let Some(enforcer) = args.retention_enforcer(thumbnails, catalog.clone()) else {
+                continue;
+            };
+
+            register!(
+                pipelines,
+                id,
+                Pipeline::<RecorderData>::singleton(
+                    Component::new()
+                        .append(Ticker::new(interval))
+                        .append(enforcer)
+                        .append(Function::new(|frame_data: RecorderData| {
+                            if let Some(error) = frame_data.get_error() {
+                                log::warn!("Unable to enforce the retention limits: {:?}", error);
+                            }
+                            Some(frame_data)
+                        })),
+                )
+            );
+        }
     }
+
+    tokio::select! {
+        _ = pipelines.run() => {}
+        // The snapshots in progress are saved first
+        _ = controller.drained() => {}
+    }
+
+    if let Some(workers) = &workers {
+        workers.flush().await;
+    }
+
+    log::info!("Shut down");
 }
 
-fn capturer(monitor_id: usize, height: u32, width: u32) -> Component<RecorderData> {
-    Component::new()
-        .append(Ticker::new(1000))
-        .append(BufferAllocator::new(
-            Buffers::CapturedScreenBuffer,
-            height as usize * width as usize * 3,
-        ))
+fn capturer_component(
+    capturer: Capturer,
+    controller: Controller,
+    args: &Args,
+    pools: &PoolRegistry<Buffers>,
+    error_pipeline: &mut Pipeline<RecorderData>,
+) -> Component<RecorderData> {
+    let component = Component::new()
+        .append(Trigger::new(controller))
+        .append(pools.get(Buffers::CapturedScreenBuffer).borrower());
+
+    let redactor = args.redactor(capturer.capture_origin());
+
+    let component = match capturer {
+        Capturer::Screen(capturer) => component.append(capturer),
+        Capturer::Desktop(capturer) => component.append(capturer),
+        Capturer::Window(capturer) => component.append(capturer),
+        Capturer::Pattern(capturer) => component.append(capturer),
+    };
+
+    let component = match redactor {
+        Some(redactor) => component.append(redactor),
+        None => component,
+    };
+
+    let component = match args.change_metric {
+        Some(metric) => component.append(
+            ChangeDetector::builder()
+                .buffer_key(Buffers::CapturedScreenBuffer)
+                .metric(metric)
+                .threshold(args.change_threshold)
+                .build(),
+        ),
+        None => component,
+    };
+
+    component.append(OnErrorSwitch::new(error_pipeline))
+}
+
+fn saver(
+    args: &Args,
+    capture_source: CaptureSource,
+    workers: Option<WorkerPool>,
+    catalog: Option<Arc<Catalog>>,
+    pools: &PoolRegistry<Buffers>,
+    error_pipeline: &mut Pipeline<RecorderData>,
+) -> Component<RecorderData> {
+    let component = Component::new().append(Function::new(|frame_data: RecorderData| {
+        let score: Option<ChangeScore> = frame_data.get(&Buffers::CapturedScreenBuffer);
+        if let Some(ChangeScore(score)) = score {
+            log::info!("Change score: {score:.4}");
+        }
+        Some(frame_data)
+    }));
+
+    // Drawn after change detection, so that the clock alone does not make frames differ
+    let monitor = capture_source
+        .monitor
+        .as_ref()
+        .map(|monitor| monitor.name.clone());
+    let component = match args.text_overlay(monitor) {
+        Some(overlay) => component
+            .append(overlay)
+            .append(OnErrorSwitch::new(error_pipeline)),
+        None => component,
+    };
+
+    let encryption = args.encryption_key();
+
+    let component = component
         .append(
-            XCapCapturer::builder()
+            ImageBufferSaver::builder()
                 .buffer_key(Buffers::CapturedScreenBuffer)
-                .monitor_id(monitor_id)
+                .sink(args.sink(false))
+                .file_name(args.file_name.clone())
+                .on_collision(args.on_collision)
+                .resume(args.resume)
+                .source(capture_source.clone())
+                .embed_metadata(!args.no_metadata)
+                .index(args.index())
+                .maybe_encryption(encryption.clone())
+                .maybe_catalog(catalog.clone())
+                .maybe_workers(workers.clone())
+                .format(args.format)
                 .build(),
         )
-}
+        .append(OnErrorSwitch::new(error_pipeline));
+
+    let component = match (&args.timelapse, args.timelapse_format()) {
+        (Some(path), Some(format)) => component
+            .append(
+                TimelapseWriter::builder()
+                    .buffer_key(Buffers::CapturedScreenBuffer)
+                    .path(path)
+                    .format(format)
+                    .frame_rate(args.timelapse_rate)
+                    .downscale(args.timelapse_downscale)
+                    .overwrite(args.timelapse_overwrite)
+                    .build(),
+            )
+            .append(OnErrorSwitch::new(error_pipeline)),
+        _ => component,
+    };
+
+    // Thumbnails are saved under the same names as the snapshots, in their own directory
+    let component = match args.thumbnail_resizer() {
+        Some(resizer) => component
+            .append(pools.get(Buffers::ThumbnailBuffer).borrower())
+            .append(resizer)
+            .append(OnErrorSwitch::new(error_pipeline))
+            .append(
+                ImageBufferSaver::builder()
+                    .buffer_key(Buffers::ThumbnailBuffer)
+                    .sink(args.sink(true))
+                    .file_name(args.file_name.clone())
+                    .on_collision(args.on_collision)
+                    .resume(args.resume)
+                    .source(capture_source)
+                    .embed_metadata(!args.no_metadata)
+                    .index(args.index())
+                    .maybe_encryption(encryption)
+                    .maybe_workers(workers.clone())
+                    .format(args.format)
+                    .build(),
+            )
+            .append(OnErrorSwitch::new(error_pipeline))
+            .append(pools.get(Buffers::ThumbnailBuffer).redeemer()),
+        None => component,
+    };
+
+    let component = match args.retention_enforcer(false, catalog) {
+        Some(enforcer) if args.retention_interval.is_none() => component
+            .append(enforcer)
+            .append(OnErrorSwitch::new(error_pipeline)),
+        _ => component,
+    };
+
+    let component = match args.retention_enforcer(true, None) {
+        Some(enforcer) if args.retention_interval.is_none() => component
+            .append(enforcer)
+            .append(OnErrorSwitch::new(error_pipeline)),
+        _ => component,
+    };
 
-fn saver(height: u32, width: u32) -> Component<RecorderData> {
-    Component::new().append(
-        PNGBufferSaver::builder()
-            .buffer_key(Buffers::CapturedScreenBuffer)
-            .path("./screenshots/")
-            .height(height)
-            .width(width)
-            .build(),
-    )
+    component.append(pools.get(Buffers::CapturedScreenBuffer).redeemer())
 }
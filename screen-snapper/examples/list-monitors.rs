@@ -0,0 +1,32 @@
+use screen_snapper::xcap_capturer::xcap_utils;
+use xcap::Monitor;
+
+fn main() {
+    env_logger::init();
+
+    let monitors = Monitor::all().expect("Unable to list the monitors");
+
+    println!(
+        "{:<9} {:<24} {:<22} {:>6} {:>9}",
+        "INDEX", "NAME", "GEOMETRY", "SCALE", "ROTATION"
+    );
+
+    for (index, monitor) in monitors.iter().enumerate() {
+        let geometry =
+            xcap_utils::monitor_geometry(monitor).expect("Unable to read the monitor geometry");
+        let is_primary = monitor.is_primary().unwrap_or(false);
+
+        println!(
+            "{:<9} {:<24} {:<22} {:>6.2} {:>8}°{}",
+            format!("index:{index}"),
+            format!("name:{}", geometry.name),
+            format!(
+                "{}x{}{:+}{:+}",
+                geometry.width, geometry.height, geometry.x, geometry.y
+            ),
+            monitor.scale_factor().unwrap_or(1.0),
+            monitor.rotation().unwrap_or(0.0),
+            if is_primary { " (primary)" } else { "" }
+        );
+    }
+}
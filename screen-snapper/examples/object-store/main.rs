@@ -0,0 +1,35 @@
+use std::{fs, path::PathBuf};
+
+use clap::Parser;
+use tiny_http::Server;
+
+mod store;
+
+/// Stand-in for an S3-compatible object store, e.g. to try the autosnapper's --s3-bucket.
+/// Buckets are directories of the root, created on the first upload. Objects are uploaded,
+/// downloaded, listed and deleted as with S3, but the requests are not authenticated.
+#[derive(Parser, Debug)]
+struct Args {
+    #[arg(long, default_value = "127.0.0.1:9000")]
+    listen: String,
+
+    /// Directory holding the buckets, created if missing
+    #[arg(long, default_value = "./object-store/")]
+    root: PathBuf,
+
+    /// Answer every Nth request with 503 Service Unavailable, to exercise retries
+    #[arg(long)]
+    fail_every: Option<u64>,
+}
+
+fn main() {
+    env_logger::init();
+
+    let args = Args::parse();
+
+    fs::create_dir_all(&args.root).expect("Unable to create the root directory");
+    let server = Server::http(&args.listen).expect("Unable to listen");
+    log::info!("Serving {} on {}", args.root.display(), args.listen);
+
+    store::serve(&server, &args.root, args.fail_every);
+}
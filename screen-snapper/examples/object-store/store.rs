@@ -0,0 +1,180 @@
+//! Request handling of the object store stand-in, shared with the integration tests
+
+use std::{
+    fs,
+    io::{self, Cursor},
+    path::Path,
+};
+
+use tiny_http::{Method, Request, Response, Server};
+
+/// Answers the requests as they come. Buckets are directories of the root,
+/// created on the first upload. With `fail_every`, every Nth request gets 503 Service Unavailable.
+pub fn serve(server: &Server, root: &Path, fail_every: Option<u64>) {
+    for (count, mut request) in (1..).zip(server.incoming_requests()) {
+        log::info!("{} {}", request.method(), request.url());
+
+        let response = if fail_every.is_some_and(|every| count % every.max(1) == 0) {
+            status(503)
+        } else {
+            handle(root, &mut request)
+        };
+
+        if let Err(err) = request.respond(response) {
+            log::warn!("Unable to respond: {err}");
+        }
+    }
+}
+
+fn handle(root: &Path, request: &mut Request) -> Response<Cursor<Vec<u8>>> {
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    let path = percent_decode(path);
+
+    let (bucket, key) = path
+        .trim_start_matches('/')
+        .split_once('/')
+        .unwrap_or((path.trim_start_matches('/'), ""));
+
+    let is_safe = |name: &str| name.split('/').all(|part| part != ".." && part != ".");
+    if bucket.is_empty() || !is_safe(bucket) || !is_safe(key) {
+        return status(400);
+    }
+
+    let bucket_path = root.join(bucket);
+
+    match (request.method(), key.is_empty()) {
+        (Method::Put, false) => {
+            let mut data = Vec::new();
+            if request.as_reader().read_to_end(&mut data).is_err() {
+                return status(400);
+            }
+
+            let path = bucket_path.join(key);
+            let written = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::write(&path, data));
+
+            match written {
+                Ok(_) => status(200),
+                Err(err) => {
+                    log::warn!("Unable to write {}: {err}", path.display());
+                    status(500)
+                }
+            }
+        }
+        (Method::Get | Method::Head, false) => match fs::read(bucket_path.join(key)) {
+            Ok(data) => Response::from_data(data),
+            Err(_) => status(404),
+        },
+        // Deleting a missing object succeeds, as with S3
+        (Method::Delete, false) => match fs::remove_file(bucket_path.join(key)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                log::warn!("Unable to delete {key}: {err}");
+                status(500)
+            }
+            _ => status(204),
+        },
+        (Method::Get, true) if bucket_path.is_dir() => list(bucket, &bucket_path, query),
+        (Method::Get, true) => status(404),
+        _ => status(405),
+    }
+}
+
+/// ListObjectsV2 response, in a single page
+fn list(bucket: &str, bucket_path: &Path, query: &str) -> Response<Cursor<Vec<u8>>> {
+    let parameter = |name: &str| {
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| percent_decode(value))
+            .unwrap_or_default()
+    };
+    let prefix = parameter("prefix");
+    let delimiter = parameter("delimiter");
+
+    let mut keys = Vec::new();
+    collect_keys(bucket_path, "", &mut keys);
+    keys.sort();
+
+    let contents: String = keys
+        .iter()
+        .filter(|(key, _)| key.starts_with(&prefix))
+        .filter(|(key, _)| delimiter.is_empty() || !key[prefix.len()..].contains(&delimiter))
+        .map(|(key, size)| {
+            format!(
+                "<Contents><Key>{}</Key><Size>{size}</Size></Contents>",
+                xml_escape(key)
+            )
+        })
+        .collect();
+
+    Response::from_string(format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+         <ListBucketResult><Name>{}</Name><Prefix>{}</Prefix><IsTruncated>false</IsTruncated>{contents}</ListBucketResult>",
+        xml_escape(bucket),
+        xml_escape(&prefix)
+    ))
+}
+
+/// Keys and sizes of the files under a directory, recursively
+fn collect_keys(directory: &Path, prefix: &str, keys: &mut Vec<(String, u64)>) {
+    let Ok(entries) = fs::read_dir(directory) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+
+        let key = format!("{prefix}{name}");
+        if metadata.is_dir() {
+            collect_keys(&entry.path(), &format!("{key}/"), keys);
+        } else {
+            keys.push((key, metadata.len()));
+        }
+    }
+}
+
+fn status(code: u16) -> Response<Cursor<Vec<u8>>> {
+    Response::from_data(Vec::new()).with_status_code(code)
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| value.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
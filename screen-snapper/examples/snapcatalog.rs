@@ -0,0 +1,318 @@
+use std::{
+    collections::{BTreeSet, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use clap::{Args as ClapArgs, Parser, Subcommand};
+use screen_snapper::catalog::{Catalog, CatalogEntry, CatalogQuery, content_hash};
+
+/// Looks up the snapshots recorded by the autosnapper's --catalog option.
+/// Times are either RFC 3339, or local times such as "2025-05-13 14:32", "2025-05-13" or "14:32".
+#[derive(Parser, Debug)]
+struct Args {
+    /// SQLite catalog of the autosnapper
+    #[arg(long)]
+    catalog: PathBuf,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// List the matching snapshots, oldest first
+    List {
+        #[command(flatten)]
+        filter: Filter,
+
+        /// Print the snapshots as JSON Lines
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Print the snapshot which was on screen at the given time
+    At {
+        #[arg(value_parser = parse_time)]
+        time: DateTime<Utc>,
+
+        #[arg(long)]
+        monitor: Option<String>,
+    },
+
+    /// Copy the matching snapshots to a directory
+    Export {
+        #[command(flatten)]
+        filter: Filter,
+
+        #[arg(long)]
+        output_dir: PathBuf,
+    },
+
+    /// Compare the catalog with the stored files: missing, modified and uncatalogued ones
+    Check {
+        /// Remove the snapshots whose files are missing from the catalog
+        #[arg(long)]
+        prune: bool,
+    },
+}
+
+#[derive(ClapArgs, Debug)]
+struct Filter {
+    /// Snapshots taken at or after this time
+    #[arg(long, value_parser = parse_time)]
+    from: Option<DateTime<Utc>>,
+
+    /// Snapshots taken before this time
+    #[arg(long, value_parser = parse_time)]
+    to: Option<DateTime<Utc>>,
+
+    #[arg(long)]
+    monitor: Option<String>,
+
+    /// The newest snapshots first
+    #[arg(long)]
+    newest_first: bool,
+
+    #[arg(long)]
+    limit: Option<usize>,
+}
+
+impl Filter {
+    fn query(self) -> CatalogQuery {
+        CatalogQuery {
+            from: self.from,
+            to: self.to,
+            monitor: self.monitor,
+            newest_first: self.newest_first,
+            limit: self.limit,
+        }
+    }
+}
+
+fn parse_time(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+
+    let naive = [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+    .or_else(|| {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .ok()
+            .map(|date| date.and_time(NaiveTime::MIN))
+    })
+    .or_else(|| {
+        ["%H:%M:%S", "%H:%M"]
+            .iter()
+            .find_map(|format| NaiveTime::parse_from_str(value, format).ok())
+            .map(|time| Local::now().date_naive().and_time(time))
+    })
+    .ok_or_else(|| format!("Invalid time: {value}"))?;
+
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|time| time.with_timezone(&Utc))
+        .ok_or_else(|| format!("Nonexistent local time: {value}"))
+}
+
+fn main() {
+    env_logger::init();
+
+    let args = Args::parse();
+
+    let Ok(catalog) = Catalog::open(&args.catalog) else {
+        eprintln!("Unable to open {}", args.catalog.display());
+        std::process::exit(1);
+    };
+
+    let succeeded = match args.command {
+        Command::List { filter, json } => list(&catalog, &filter.query(), json),
+        Command::At { time, monitor } => at(&catalog, &time, monitor.as_deref()),
+        Command::Export { filter, output_dir } => export(&catalog, &filter.query(), &output_dir),
+        Command::Check { prune } => check(&catalog, prune),
+    };
+
+    if !succeeded {
+        std::process::exit(1);
+    }
+}
+
+fn print_entry(entry: &CatalogEntry, json: bool) {
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string(entry).expect("Entries are serializable")
+        );
+        return;
+    }
+
+    let change = entry
+        .change_score
+        .map(|score| format!(" change {score:.4}"))
+        .unwrap_or_default();
+    println!(
+        "{} {} {}x{} {}{} {}",
+        entry
+            .time
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M:%S%.3f"),
+        entry.monitor.as_deref().unwrap_or("-"),
+        entry.width,
+        entry.height,
+        &entry.sha256[..12],
+        change,
+        entry.location
+    );
+}
+
+fn list(catalog: &Catalog, query: &CatalogQuery, json: bool) -> bool {
+    match catalog.query(query) {
+        Ok(entries) => {
+            for entry in &entries {
+                print_entry(entry, json);
+            }
+            true
+        }
+        Err(error) => {
+            eprintln!("Unable to query the catalog: {:?}", error);
+            false
+        }
+    }
+}
+
+fn at(catalog: &Catalog, time: &DateTime<Utc>, monitor: Option<&str>) -> bool {
+    match catalog.at(time, monitor) {
+        Ok(Some(entry)) => {
+            print_entry(&entry, false);
+            true
+        }
+        Ok(None) => {
+            eprintln!("No snapshot was taken by then");
+            false
+        }
+        Err(error) => {
+            eprintln!("Unable to query the catalog: {:?}", error);
+            false
+        }
+    }
+}
+
+/// Location of a snapshot as a local file, if stored as one
+fn local_path(entry: &CatalogEntry) -> Option<&Path> {
+    (!entry.location.contains("://")).then(|| Path::new(&entry.location))
+}
+
+fn export(catalog: &Catalog, query: &CatalogQuery, output_dir: &Path) -> bool {
+    let entries = match catalog.query(query) {
+        Ok(entries) => entries,
+        Err(error) => {
+            eprintln!("Unable to query the catalog: {:?}", error);
+            return false;
+        }
+    };
+
+    if let Err(err) = fs::create_dir_all(output_dir) {
+        eprintln!("Unable to create {}: {err}", output_dir.display());
+        return false;
+    }
+
+    let mut succeeded = true;
+    for entry in &entries {
+        let Some((path, name)) = local_path(entry).and_then(|path| Some((path, path.file_name()?)))
+        else {
+            eprintln!("Skipping {}, not a local file", entry.location);
+            succeeded = false;
+            continue;
+        };
+
+        let output = output_dir.join(name);
+        match fs::copy(path, &output) {
+            Ok(_) => println!("{} -> {}", path.display(), output.display()),
+            Err(err) => {
+                eprintln!("Unable to copy {}: {err}", path.display());
+                succeeded = false;
+            }
+        }
+    }
+
+    succeeded
+}
+
+fn check(catalog: &Catalog, prune: bool) -> bool {
+    let entries = match catalog.query(&CatalogQuery::default()) {
+        Ok(entries) => entries,
+        Err(error) => {
+            eprintln!("Unable to query the catalog: {:?}", error);
+            return false;
+        }
+    };
+
+    let mut drifted = false;
+    let mut unchecked = 0;
+    let mut catalogued = HashSet::new();
+    // Directories and extensions of the catalogued files, to look for uncatalogued ones
+    let mut directories = BTreeSet::new();
+
+    for entry in &entries {
+        let Some(path) = local_path(entry) else {
+            unchecked += 1;
+            continue;
+        };
+
+        catalogued.insert(path.to_path_buf());
+        if let (Some(directory), Some(extension)) = (path.parent(), path.extension()) {
+            directories.insert((directory.to_path_buf(), extension.to_os_string()));
+        }
+
+        match fs::read(path) {
+            Ok(data) if content_hash(&data) == entry.sha256 => {}
+            Ok(_) => {
+                println!("modified: {}", path.display());
+                drifted = true;
+            }
+            Err(_) => {
+                println!("missing: {}", path.display());
+                drifted = true;
+
+                if prune && catalog.remove(&entry.location).is_ok() {
+                    println!("pruned: {}", path.display());
+                }
+            }
+        }
+    }
+
+    for (directory, extension) in &directories {
+        let Ok(files) = fs::read_dir(directory) else {
+            continue;
+        };
+
+        for path in files.flatten().map(|file| file.path()) {
+            if path.is_file()
+                && path.extension() == Some(extension.as_os_str())
+                && !catalogued.contains(&path)
+            {
+                println!("uncatalogued: {}", path.display());
+                drifted = true;
+            }
+        }
+    }
+
+    if unchecked > 0 {
+        println!("{unchecked} snapshots not stored as local files were not checked");
+    }
+
+    if !drifted {
+        println!("{} snapshots match the catalog", entries.len() - unchecked);
+    }
+
+    !drifted
+}
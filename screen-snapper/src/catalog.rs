@@ -0,0 +1,248 @@
+use std::{
+    path::Path,
+    sync::{Mutex, MutexGuard},
+    time::Duration,
+};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{Connection, OptionalExtension, Row, ToSql, params};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::{error::Error, metadata::SnapshotMetadata};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS snapshots (
+        id INTEGER PRIMARY KEY,
+        location TEXT NOT NULL UNIQUE,
+        time TEXT NOT NULL,
+        host TEXT NOT NULL,
+        monitor TEXT,
+        width INTEGER NOT NULL,
+        height INTEGER NOT NULL,
+        size INTEGER NOT NULL,
+        sha256 TEXT NOT NULL,
+        change_score REAL
+    );
+    CREATE INDEX IF NOT EXISTS snapshots_time ON snapshots (time);
+    CREATE INDEX IF NOT EXISTS snapshots_monitor_time ON snapshots (monitor, time);
+";
+
+const COLUMNS: &str = "location, time, host, monitor, width, height, size, sha256, change_score";
+
+/// Saved snapshot, as recorded in the catalog
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CatalogEntry {
+    /// Where the snapshot is stored, e.g. a file path
+    pub location: String,
+    pub time: DateTime<Utc>,
+    pub host: String,
+    pub monitor: Option<String>,
+    pub width: u32,
+    pub height: u32,
+    /// Bytes stored
+    pub size: u64,
+    /// Hash of the stored bytes, as hexadecimal
+    pub sha256: String,
+    pub change_score: Option<f64>,
+}
+
+impl CatalogEntry {
+    pub fn new(
+        location: String,
+        metadata: &SnapshotMetadata,
+        data: &[u8],
+        change_score: Option<f64>,
+    ) -> Self {
+        Self {
+            location,
+            time: metadata.time.with_timezone(&Utc),
+            host: metadata.host.clone(),
+            monitor: metadata
+                .monitor
+                .as_ref()
+                .map(|monitor| monitor.name.clone()),
+            width: metadata.width,
+            height: metadata.height,
+            size: data.len() as u64,
+            sha256: content_hash(data),
+            change_score,
+        }
+    }
+
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let time: String = row.get(1)?;
+        let time = DateTime::parse_from_rfc3339(&time)
+            .map_err(|err| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    1,
+                    rusqlite::types::Type::Text,
+                    err.into(),
+                )
+            })?
+            .with_timezone(&Utc);
+
+        Ok(Self {
+            location: row.get(0)?,
+            time,
+            host: row.get(2)?,
+            monitor: row.get(3)?,
+            width: row.get(4)?,
+            height: row.get(5)?,
+            size: row.get(6)?,
+            sha256: row.get(7)?,
+            change_score: row.get(8)?,
+        })
+    }
+}
+
+/// Hash of a stored snapshot, as recorded in the catalog
+pub fn content_hash(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// Times are stored in UTC with a fixed precision, so that they sort as text
+fn time_text(time: &DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// Snapshots to look up, unset criteria being ignored
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CatalogQuery {
+    /// Snapshots taken at or after this time
+    pub from: Option<DateTime<Utc>>,
+    /// Snapshots taken before this time
+    pub to: Option<DateTime<Utc>>,
+    pub monitor: Option<String>,
+    pub newest_first: bool,
+    pub limit: Option<usize>,
+}
+
+/// SQLite database holding a row per saved snapshot, to find them by time or monitor.
+/// It may be shared by several savers, and read while they are running.
+pub struct Catalog {
+    connection: Mutex<Connection>,
+}
+
+impl Catalog {
+    /// Opens a catalog, creating it if missing
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        let connection = Connection::open(path).map_err(|err| Error::CatalogFailed.logged(err))?;
+
+        connection
+            .busy_timeout(Duration::from_secs(5))
+            .and_then(|_| connection.pragma_update(None, "journal_mode", "WAL"))
+            .and_then(|_| connection.execute_batch(SCHEMA))
+            .map_err(|err| Error::CatalogFailed.logged(err))?;
+
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    fn connection(&self) -> MutexGuard<'_, Connection> {
+        self.connection
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Records a snapshot, replacing any previous one stored at the same location
+    pub fn record(&self, entry: &CatalogEntry) -> Result<(), Error> {
+        self.connection()
+            .execute(
+                &format!(
+                    "INSERT OR REPLACE INTO snapshots ({COLUMNS}) \
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)"
+                ),
+                params![
+                    entry.location,
+                    time_text(&entry.time),
+                    entry.host,
+                    entry.monitor,
+                    entry.width,
+                    entry.height,
+                    entry.size,
+                    entry.sha256,
+                    entry.change_score,
+                ],
+            )
+            .map_err(|err| Error::CatalogFailed.logged(err))?;
+
+        Ok(())
+    }
+
+    /// Forgets the snapshot stored at a location, returning whether it was recorded
+    pub fn remove(&self, location: &str) -> Result<bool, Error> {
+        let removed = self
+            .connection()
+            .execute("DELETE FROM snapshots WHERE location = ?1", [location])
+            .map_err(|err| Error::CatalogFailed.logged(err))?;
+
+        Ok(removed > 0)
+    }
+
+    /// Snapshots matching the query, in the order they were taken unless asked otherwise
+    pub fn query(&self, query: &CatalogQuery) -> Result<Vec<CatalogEntry>, Error> {
+        let mut conditions = Vec::new();
+        let mut values: Vec<Box<dyn ToSql>> = Vec::new();
+
+        if let Some(from) = &query.from {
+            conditions.push("time >= ?");
+            values.push(Box::new(time_text(from)));
+        }
+        if let Some(to) = &query.to {
+            conditions.push("time < ?");
+            values.push(Box::new(time_text(to)));
+        }
+        if let Some(monitor) = &query.monitor {
+            conditions.push("monitor = ?");
+            values.push(Box::new(monitor.clone()));
+        }
+
+        let mut sql = format!("SELECT {COLUMNS} FROM snapshots");
+        if !conditions.is_empty() {
+            sql += &format!(" WHERE {}", conditions.join(" AND "));
+        }
+        sql += if query.newest_first {
+            " ORDER BY time DESC, id DESC"
+        } else {
+            " ORDER BY time, id"
+        };
+        if let Some(limit) = query.limit {
+            sql += &format!(" LIMIT {limit}");
+        }
+
+        let connection = self.connection();
+        let mut statement = connection
+            .prepare(&sql)
+            .map_err(|err| Error::CatalogFailed.logged(err))?;
+
+        statement
+            .query_map(
+                rusqlite::params_from_iter(values.iter().map(|value| value.as_ref())),
+                CatalogEntry::from_row,
+            )
+            .and_then(|rows| rows.collect())
+            .map_err(|err| Error::CatalogFailed.logged(err))
+    }
+
+    /// Snapshot which was the latest at the given time, i.e. what was on screen then
+    pub fn at(
+        &self,
+        time: &DateTime<Utc>,
+        monitor: Option<&str>,
+    ) -> Result<Option<CatalogEntry>, Error> {
+        self.connection()
+            .query_row(
+                &format!(
+                    "SELECT {COLUMNS} FROM snapshots \
+                     WHERE time <= ?1 AND (?2 IS NULL OR monitor = ?2) \
+                     ORDER BY time DESC, id DESC LIMIT 1"
+                ),
+                params![time_text(time), monitor],
+                CatalogEntry::from_row,
+            )
+            .optional()
+            .map_err(|err| Error::CatalogFailed.logged(err))
+    }
+}
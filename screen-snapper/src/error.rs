@@ -0,0 +1,32 @@
+use std::fmt::Display;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    MissingBuffer,
+    MissingFormat,
+    BackendUnavailable,
+    MonitorNotFound,
+    WindowNotFound,
+    CaptureFailed,
+    SizeMismatch,
+    RegionOutOfBounds,
+    Unchanged,
+    EncodeFailed,
+    EncryptFailed,
+    DecryptFailed,
+    InvalidKey,
+    WriteFailed,
+    UploadFailed,
+    StorageUnavailable,
+    RetentionFailed,
+    CatalogFailed,
+    ControlFailed,
+}
+
+impl Error {
+    /// Logs the underlying cause, which is not carried by the error itself
+    pub(crate) fn logged(self, cause: impl Display) -> Self {
+        log::error!("{:?}: {}", self, cause);
+        self
+    }
+}
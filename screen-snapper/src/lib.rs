@@ -1,2 +1,25 @@
-pub mod png_saver;
+pub mod catalog;
+pub mod change_detector;
+pub mod control;
+pub mod encryption;
+pub mod error;
+pub mod file_name;
+pub mod geometry;
+pub mod image_saver;
+pub mod layout;
+pub mod metadata;
+pub mod monitor_selector;
+pub mod overlay;
+pub mod pattern_capturer;
+pub mod pixel_format;
+pub mod redaction;
+pub mod region;
+pub mod resize;
+pub mod retention;
+pub mod s3_storage;
+pub mod storage;
+pub mod timelapse;
+pub mod worker_pool;
 pub mod xcap_capturer;
+pub mod xcap_multi_capturer;
+pub mod xcap_window_capturer;
@@ -0,0 +1,62 @@
+use std::{fmt::Display, str::FromStr};
+
+/// Picks a monitor without relying on the order in which the OS lists them
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum MonitorSelector {
+    #[default]
+    Primary,
+    /// Connector or friendly name, compared case-insensitively
+    Name(String),
+    /// Monitor containing the given desktop coordinates
+    Point(i32, i32),
+    /// Position in the monitor list, which may change across reboots or hotplugs
+    Index(usize),
+}
+
+impl Display for MonitorSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Primary => write!(f, "primary"),
+            Self::Name(name) => write!(f, "name:{name}"),
+            Self::Point(x, y) => write!(f, "point:{x},{y}"),
+            Self::Index(index) => write!(f, "index:{index}"),
+        }
+    }
+}
+
+/// Parses "primary", "point:<x>,<y>", "index:<n>" or "name:<name>",
+/// bare numbers being taken as indices and anything else as a name
+impl FromStr for MonitorSelector {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value == "primary" {
+            return Ok(Self::Primary);
+        }
+
+        if let Some(point) = value.strip_prefix("point:") {
+            let invalid = || format!("Invalid point (expected X,Y): {point}");
+            let (x, y) = point.split_once(',').ok_or_else(invalid)?;
+            return Ok(Self::Point(
+                x.trim().parse().map_err(|_| invalid())?,
+                y.trim().parse().map_err(|_| invalid())?,
+            ));
+        }
+
+        if let Some(index) = value.strip_prefix("index:") {
+            return index
+                .parse()
+                .map(Self::Index)
+                .map_err(|_| format!("Invalid monitor index: {index}"));
+        }
+
+        if let Some(name) = value.strip_prefix("name:") {
+            return Ok(Self::Name(name.to_string()));
+        }
+
+        Ok(match value.parse() {
+            Ok(index) => Self::Index(index),
+            Err(_) => Self::Name(value.to_string()),
+        })
+    }
+}
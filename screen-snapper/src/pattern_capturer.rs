@@ -0,0 +1,329 @@
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use bon::Builder;
+use remotia::{
+    buffers::BytesMut,
+    traits::{FrameError, FrameProcessor, FrameProperties, PullableFrameProperties},
+};
+
+use crate::{
+    error::Error,
+    geometry::{GeometryChange, GeometryTracker},
+    pixel_format::{FrameFormat, PixelFormat},
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Pattern {
+    #[default]
+    SmpteBars,
+    MovingBox,
+    Gradient,
+    Noise,
+}
+
+impl FromStr for Pattern {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "smpte-bars" => Ok(Self::SmpteBars),
+            "moving-box" => Ok(Self::MovingBox),
+            "gradient" => Ok(Self::Gradient),
+            "noise" => Ok(Self::Noise),
+            _ => Err(format!("Unknown test pattern: {value}")),
+        }
+    }
+}
+
+/// Size the pattern switches to and back every few frames, simulating a display changing resolution
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PatternResize {
+    pub width: u32,
+    pub height: u32,
+    /// Frames between two switches
+    pub every: u64,
+}
+
+/// Parses "WIDTHxHEIGHT@FRAMES", e.g. "1920x1080@30"
+impl FromStr for PatternResize {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid pattern resize (expected WIDTHxHEIGHT@FRAMES): {value}");
+
+        let (size, every) = value.split_once('@').ok_or_else(invalid)?;
+        let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+        let resize = Self {
+            width: width.parse().map_err(|_| invalid())?,
+            height: height.parse().map_err(|_| invalid())?,
+            every: every.parse().map_err(|_| invalid())?,
+        };
+
+        if resize.width == 0 || resize.height == 0 || resize.every == 0 {
+            return Err(invalid());
+        }
+
+        Ok(resize)
+    }
+}
+
+/// Headless capturer which fills the buffer with a synthetic test pattern
+#[derive(Builder)]
+pub struct PatternCapturer<K> {
+    #[builder(field = 0)]
+    frame_index: u64,
+
+    #[builder(field = 0x2545_F491_4F6C_DD1D)]
+    noise_state: u64,
+
+    #[builder(skip)]
+    tracker: GeometryTracker,
+
+    buffer_key: K,
+
+    width: u32,
+    height: u32,
+
+    /// Alternate between the size above and this one
+    resize: Option<PatternResize>,
+
+    #[builder(default)]
+    pattern: Pattern,
+
+    #[builder(default)]
+    pixel_format: PixelFormat,
+
+    #[builder(default = true)]
+    counter: bool,
+}
+
+impl<K> PatternCapturer<K> {
+    pub fn capture_size(&self) -> (u32, u32) {
+        (self.height, self.width)
+    }
+
+    pub fn frame_format(&self) -> FrameFormat {
+        FrameFormat::packed(self.pixel_format, self.width, self.height)
+    }
+
+    pub fn buffer_size(&self) -> usize {
+        self.frame_format().buffer_size()
+    }
+
+    /// Switches to the alternate size, or back, once every `every` frames
+    fn follow_resize(&mut self) {
+        let Some(resize) = &mut self.resize else {
+            return;
+        };
+
+        if self.frame_index > 0 && self.frame_index.is_multiple_of(resize.every) {
+            std::mem::swap(&mut self.width, &mut resize.width);
+            std::mem::swap(&mut self.height, &mut resize.height);
+        }
+    }
+
+    fn render(&mut self, pixels: &mut [u8]) {
+        let bytes_per_pixel = self.pixel_format.bytes_per_pixel();
+        let moving_box = self.moving_box();
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let rgb = match self.pattern {
+                    Pattern::SmpteBars => smpte_bars(x, y, self.width, self.height),
+                    Pattern::MovingBox => {
+                        let (box_x, box_y, size) = moving_box;
+                        if (box_x..box_x + size).contains(&x) && (box_y..box_y + size).contains(&y)
+                        {
+                            [230, 120, 20]
+                        } else {
+                            [32, 32, 32]
+                        }
+                    }
+                    Pattern::Gradient => [
+                        (x * 255 / self.width.max(2).saturating_sub(1)) as u8,
+                        (y * 255 / self.height.max(2).saturating_sub(1)) as u8,
+                        self.frame_index as u8,
+                    ],
+                    Pattern::Noise => {
+                        let value = self.next_noise();
+                        [value as u8, (value >> 8) as u8, (value >> 16) as u8]
+                    }
+                };
+
+                let offset = (y as usize * self.width as usize + x as usize) * bytes_per_pixel;
+                self.pixel_format
+                    .write(&mut pixels[offset..offset + bytes_per_pixel], rgb);
+            }
+        }
+
+        if self.counter {
+            self.draw_counter(pixels);
+        }
+    }
+
+    fn moving_box(&self) -> (u32, u32, u32) {
+        let size = (self.width.min(self.height) / 4).max(1);
+        let x = bounce(self.frame_index * 8, self.width.saturating_sub(size));
+        let y = bounce(self.frame_index * 6, self.height.saturating_sub(size));
+        (x, y, size)
+    }
+
+    fn next_noise(&mut self) -> u64 {
+        self.noise_state ^= self.noise_state << 13;
+        self.noise_state ^= self.noise_state >> 7;
+        self.noise_state ^= self.noise_state << 17;
+        self.noise_state
+    }
+
+    fn draw_counter(&self, pixels: &mut [u8]) {
+        let digits = self.frame_index.to_string();
+        let scale = (self.height / 90).max(1);
+        let margin = 2 * scale;
+
+        let box_width = margin * 2 + digits.len() as u32 * 4 * scale - scale;
+        let box_height = margin * 2 + 5 * scale;
+        for y in 0..box_height {
+            for x in 0..box_width {
+                self.put_pixel(pixels, x, y, [0, 0, 0]);
+            }
+        }
+
+        for (index, digit) in digits.bytes().enumerate() {
+            let glyph = DIGIT_GLYPHS[(digit - b'0') as usize];
+            let origin_x = margin + index as u32 * 4 * scale;
+
+            for (row, bits) in glyph.iter().enumerate() {
+                for column in 0..3 {
+                    if bits & (0b100 >> column) == 0 {
+                        continue;
+                    }
+
+                    for dy in 0..scale {
+                        for dx in 0..scale {
+                            self.put_pixel(
+                                pixels,
+                                origin_x + column * scale + dx,
+                                margin + row as u32 * scale + dy,
+                                [255, 255, 255],
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn put_pixel(&self, pixels: &mut [u8], x: u32, y: u32, rgb: [u8; 3]) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+
+        let bytes_per_pixel = self.pixel_format.bytes_per_pixel();
+        let offset = (y as usize * self.width as usize + x as usize) * bytes_per_pixel;
+        self.pixel_format
+            .write(&mut pixels[offset..offset + bytes_per_pixel], rgb);
+    }
+}
+
+// 3x5 glyphs for the frame counter, one bit per column
+const DIGIT_GLYPHS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+fn bounce(position: u64, range: u32) -> u32 {
+    if range == 0 {
+        return 0;
+    }
+
+    let range = range as u64;
+    let position = position % (2 * range);
+    if position < range {
+        position as u32
+    } else {
+        (2 * range - position) as u32
+    }
+}
+
+fn smpte_bars(x: u32, y: u32, width: u32, height: u32) -> [u8; 3] {
+    const TOP: [[u8; 3]; 7] = [
+        [191, 191, 191],
+        [191, 191, 0],
+        [0, 191, 191],
+        [0, 191, 0],
+        [191, 0, 191],
+        [191, 0, 0],
+        [0, 0, 191],
+    ];
+    const MIDDLE: [[u8; 3]; 7] = [
+        [0, 0, 191],
+        [19, 19, 19],
+        [191, 0, 191],
+        [19, 19, 19],
+        [0, 191, 191],
+        [19, 19, 19],
+        [191, 191, 191],
+    ];
+    const BOTTOM: [[u8; 3]; 4] = [[0, 33, 76], [255, 255, 255], [50, 0, 106], [19, 19, 19]];
+    const PLUGE: [[u8; 3]; 3] = [[9, 9, 9], [19, 19, 19], [29, 29, 29]];
+
+    let bar = (x as u64 * 7 / width as u64) as usize;
+
+    if y < height * 2 / 3 {
+        TOP[bar]
+    } else if y < height * 3 / 4 {
+        MIDDLE[bar]
+    } else {
+        // The first five bars are split in four, the sixth one holds the PLUGE steps
+        let step = (x as u64 * 28 / width as u64) as usize;
+        match bar {
+            0..=4 => BOTTOM[(step * 4 / 20).min(3)],
+            5 => PLUGE[(x as u64 * 21 / width as u64 - 15).min(2) as usize],
+            _ => [19, 19, 19],
+        }
+    }
+}
+
+#[async_trait]
+impl<K, F> FrameProcessor<F> for PatternCapturer<K>
+where
+    F: Send + 'static,
+    K: Send + Copy,
+    F: PullableFrameProperties<K, BytesMut>
+        + FrameProperties<K, FrameFormat>
+        + FrameProperties<K, GeometryChange>
+        + FrameError<Error>,
+{
+    async fn process(&mut self, mut dto: F) -> Option<F> {
+        log::debug!("Generating test pattern frame #{}...", self.frame_index);
+
+        self.follow_resize();
+
+        let Some(mut buffer) = dto.pull(&self.buffer_key) else {
+            dto.report_error(Error::MissingBuffer);
+            return Some(dto);
+        };
+
+        buffer.clear();
+        buffer.resize(self.buffer_size(), 0);
+        self.render(&mut buffer);
+
+        dto.push(self.buffer_key, buffer);
+
+        let format = self.frame_format();
+        self.tracker.declare(&mut dto, self.buffer_key, format);
+
+        self.frame_index += 1;
+
+        Some(dto)
+    }
+}
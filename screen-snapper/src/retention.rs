@@ -0,0 +1,191 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use bon::Builder;
+use chrono::{DateTime, Local};
+use remotia::traits::{FrameError, FrameProcessor};
+use serde::Deserialize;
+
+use crate::{
+    catalog::Catalog,
+    error::Error,
+    metadata::INDEX_FILE_NAME,
+    storage::{ReadPosition, StorageSink},
+};
+
+/// Limits on the snapshots kept in a sink, unset limits being ignored
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    pub max_count: Option<usize>,
+    pub max_bytes: Option<u64>,
+    pub max_age: Option<Duration>,
+}
+
+impl RetentionPolicy {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_count.is_none() && self.max_bytes.is_none() && self.max_age.is_none()
+    }
+}
+
+/// Fields of the index lines telling which snapshot was saved when
+#[derive(Deserialize)]
+struct IndexEntry {
+    file: String,
+    time: DateTime<Local>,
+}
+
+/// Snapshots listed in the index, read incrementally
+#[derive(Default)]
+struct Recorded {
+    position: ReadPosition,
+    /// Capture time of the snapshots not deleted yet, by key
+    snapshots: HashMap<String, DateTime<Local>>,
+}
+
+struct Snapshot {
+    key: String,
+    time: DateTime<Local>,
+    size: u64,
+}
+
+/// Deletes the oldest snapshots of a sink until the policy is met.
+/// Only the snapshots listed in the index of the sink are considered, so that objects
+/// not saved by an `ImageBufferSaver` with its index enabled are never touched.
+#[derive(Builder, Clone)]
+pub struct RetentionEnforcer {
+    /// Sink the saver stores the snapshots and their index in
+    sink: Arc<dyn StorageSink>,
+
+    policy: RetentionPolicy,
+
+    /// Catalog the deleted snapshots are removed from
+    catalog: Option<Arc<Catalog>>,
+
+    #[builder(skip)]
+    recorded: Arc<Mutex<Recorded>>,
+}
+
+impl RetentionEnforcer {
+    /// Stored snapshots of the index, oldest first
+    fn snapshots(&self, recorded: &mut Recorded) -> Result<Vec<Snapshot>, Error> {
+        let (lines, position) = self
+            .sink
+            .read_lines(INDEX_FILE_NAME, &recorded.position)
+            .map_err(|_| Error::RetentionFailed)?;
+
+        for line in lines {
+            match serde_json::from_str::<IndexEntry>(&line) {
+                Ok(entry) => {
+                    recorded.snapshots.insert(entry.file, entry.time);
+                }
+                Err(err) => log::warn!("Skipping an unreadable index line: {}", err),
+            }
+        }
+        recorded.position = position;
+
+        let sizes: HashMap<String, u64> = self
+            .sink
+            .list()
+            .map_err(|_| Error::RetentionFailed)?
+            .into_iter()
+            .map(|object| (object.key, object.size))
+            .collect();
+
+        // Forgets the snapshots deleted in the meantime, e.g. by hand
+        recorded.snapshots.retain(|key, _| sizes.contains_key(key));
+
+        let mut snapshots: Vec<Snapshot> = recorded
+            .snapshots
+            .iter()
+            .map(|(key, time)| Snapshot {
+                key: key.clone(),
+                time: *time,
+                size: sizes[key],
+            })
+            .collect();
+
+        snapshots.sort_by(|a, b| a.time.cmp(&b.time).then(a.key.cmp(&b.key)));
+        Ok(snapshots)
+    }
+
+    /// Deletes the snapshots exceeding the policy, returning how many were deleted.
+    /// Calls the sink, hence may block.
+    pub fn enforce(&self) -> Result<usize, Error> {
+        if self.policy.is_unlimited() {
+            return Ok(0);
+        }
+
+        let mut recorded = self.recorded.lock().unwrap_or_else(|err| err.into_inner());
+        let snapshots = self.snapshots(&mut recorded)?;
+        let now = Local::now();
+
+        let mut count = snapshots.len();
+        let mut bytes: u64 = snapshots.iter().map(|snapshot| snapshot.size).sum();
+        let mut deleted = 0;
+
+        for snapshot in snapshots {
+            let expired = self.policy.max_age.is_some_and(|max_age| {
+                (now - snapshot.time)
+                    .to_std()
+                    .is_ok_and(|age| age > max_age)
+            });
+            let too_many = self.policy.max_count.is_some_and(|max| count > max);
+            let too_large = self.policy.max_bytes.is_some_and(|max| bytes > max);
+
+            if !(expired || too_many || too_large) {
+                // Newer snapshots are within the age limit, and the others are met
+                break;
+            }
+
+            let location = self.sink.location(&snapshot.key);
+            match self.sink.delete(&snapshot.key) {
+                Ok(()) => {
+                    log::debug!("Deleted {}", location);
+                    if let Some(catalog) = &self.catalog {
+                        let _ = catalog.remove(&location);
+                    }
+                    recorded.snapshots.remove(&snapshot.key);
+                    count -= 1;
+                    bytes -= snapshot.size;
+                    deleted += 1;
+                }
+                Err(error) => log::warn!("Unable to delete {}: {:?}", location, error),
+            }
+        }
+
+        if deleted > 0 {
+            log::info!(
+                "Deleted {} snapshots from {}, {} left ({} bytes)",
+                deleted,
+                self.sink.location(""),
+                count,
+                bytes
+            );
+        }
+
+        Ok(deleted)
+    }
+}
+
+#[async_trait]
+impl<F> FrameProcessor<F> for RetentionEnforcer
+where
+    F: FrameError<Error> + Send + 'static,
+{
+    async fn process(&mut self, mut frame_data: F) -> Option<F> {
+        let enforcer = self.clone();
+        let result = tokio::task::spawn_blocking(move || enforcer.enforce())
+            .await
+            .unwrap_or_else(|err| Err(Error::RetentionFailed.logged(err)));
+
+        if let Err(error) = result {
+            frame_data.report_error(error);
+        }
+
+        Some(frame_data)
+    }
+}
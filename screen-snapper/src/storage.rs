@@ -0,0 +1,274 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::PathBuf,
+    sync::{
+        Arc, Mutex, MutexGuard,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use crate::error::Error;
+
+/// Object of a sink, as listed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StoredObject {
+    pub key: String,
+    /// In bytes
+    pub size: u64,
+}
+
+/// How far the lines appended to an object were read, starting from the default position
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReadPosition {
+    /// Bytes read from the start of the object
+    pub offset: u64,
+}
+
+/// Destination of the encoded snapshots, holding objects named by keys such as "1.png".
+/// Sinks are shared with the saving workers, hence blocking and thread-safe.
+pub trait StorageSink: Send + Sync {
+    /// Stores an object, replacing any previous one under the same key
+    fn put(&self, key: &str, data: &[u8]) -> Result<(), Error>;
+
+    /// Appends to an object, creating it if missing
+    fn append(&self, key: &str, data: &[u8]) -> Result<(), Error>;
+
+    /// Contents of an object, including the data appended to it, `None` if missing
+    fn read(&self, key: &str) -> Result<Option<Vec<u8>>, Error>;
+
+    /// Complete lines appended to an object since the given position, and the position
+    /// following them. Missing objects have no lines.
+    fn read_lines(
+        &self,
+        key: &str,
+        from: &ReadPosition,
+    ) -> Result<(Vec<String>, ReadPosition), Error> {
+        let data = self.read(key)?.unwrap_or_default();
+        let unread = data.get(from.offset as usize..).unwrap_or_default();
+
+        Ok(complete_lines(unread, from))
+    }
+
+    fn exists(&self, key: &str) -> Result<bool, Error>;
+
+    /// Removes an object, if it exists
+    fn delete(&self, key: &str) -> Result<(), Error>;
+
+    /// Stored objects, in no particular order
+    fn list(&self) -> Result<Vec<StoredObject>, Error>;
+
+    /// Where an object is stored, for logging
+    fn location(&self, key: &str) -> String;
+}
+
+/// Lines of data read from a position, up to the last line feed
+pub(crate) fn complete_lines(data: &[u8], from: &ReadPosition) -> (Vec<String>, ReadPosition) {
+    let complete = data
+        .iter()
+        .rposition(|&byte| byte == b'\n')
+        .map_or(0, |index| index + 1);
+
+    let lines = String::from_utf8_lossy(&data[..complete])
+        .lines()
+        .map(|line| line.to_string())
+        .collect();
+
+    let position = ReadPosition {
+        offset: from.offset + complete as u64,
+    };
+
+    (lines, position)
+}
+
+/// Stores the objects as files of a directory, which is expected to exist
+pub struct FileSystemSink {
+    directory: PathBuf,
+}
+
+impl FileSystemSink {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+}
+
+impl StorageSink for FileSystemSink {
+    /// Writes a hidden temporary file first, renamed once complete,
+    /// so that readers never see a partially written object.
+    /// Each write has its own temporary file, even when writing the same key.
+    fn put(&self, key: &str, data: &[u8]) -> Result<(), Error> {
+        static WRITES: AtomicU64 = AtomicU64::new(0);
+
+        let path = self.directory.join(key);
+        let temporary = self.directory.join(format!(
+            ".{key}.{}.{}.tmp",
+            std::process::id(),
+            WRITES.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let result = fs::write(&temporary, data).and_then(|_| fs::rename(&temporary, &path));
+        if result.is_err() {
+            let _ = fs::remove_file(&temporary);
+        }
+
+        result.map_err(|err| Error::WriteFailed.logged(err))
+    }
+
+    fn append(&self, key: &str, data: &[u8]) -> Result<(), Error> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.directory.join(key))
+            .and_then(|mut file| file.write_all(data))
+            .map_err(|err| Error::WriteFailed.logged(err))
+    }
+
+    fn read(&self, key: &str) -> Result<Option<Vec<u8>>, Error> {
+        match fs::read(self.directory.join(key)) {
+            Ok(data) => Ok(Some(data)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(Error::StorageUnavailable.logged(err)),
+        }
+    }
+
+    /// Reads the file from the position on, rather than from its start
+    fn read_lines(
+        &self,
+        key: &str,
+        from: &ReadPosition,
+    ) -> Result<(Vec<String>, ReadPosition), Error> {
+        let mut file = match File::open(self.directory.join(key)) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Ok((Vec::new(), from.clone()));
+            }
+            Err(err) => return Err(Error::StorageUnavailable.logged(err)),
+        };
+
+        let mut unread = Vec::new();
+        file.seek(SeekFrom::Start(from.offset))
+            .and_then(|_| file.read_to_end(&mut unread))
+            .map_err(|err| Error::StorageUnavailable.logged(err))?;
+
+        Ok(complete_lines(&unread, from))
+    }
+
+    fn exists(&self, key: &str) -> Result<bool, Error> {
+        fs::exists(self.directory.join(key)).map_err(|err| Error::StorageUnavailable.logged(err))
+    }
+
+    fn delete(&self, key: &str) -> Result<(), Error> {
+        match fs::remove_file(self.directory.join(key)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                Err(Error::WriteFailed.logged(err))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Regular files of the directory, temporary ones excluded.
+    /// Symbolic links and subdirectories are left out.
+    fn list(&self) -> Result<Vec<StoredObject>, Error> {
+        let entries =
+            fs::read_dir(&self.directory).map_err(|err| Error::StorageUnavailable.logged(err))?;
+
+        Ok(entries
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                if !entry.file_type().ok()?.is_file() {
+                    return None;
+                }
+
+                let key = entry
+                    .file_name()
+                    .into_string()
+                    .ok()
+                    .filter(|name| !name.starts_with('.'))?;
+
+                Some(StoredObject {
+                    key,
+                    size: entry.metadata().ok()?.len(),
+                })
+            })
+            .collect())
+    }
+
+    fn location(&self, key: &str) -> String {
+        self.directory.join(key).display().to_string()
+    }
+}
+
+/// Keeps the objects in memory, e.g. to check what a pipeline saved.
+/// Clones share the same objects.
+#[derive(Clone, Default)]
+pub struct MemorySink {
+    objects: Arc<Mutex<BTreeMap<String, Vec<u8>>>>,
+}
+
+impl MemorySink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn objects(&self) -> MutexGuard<'_, BTreeMap<String, Vec<u8>>> {
+        self.objects.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    pub fn get(&self, key: &str) -> Option<Vec<u8>> {
+        self.objects().get(key).cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects().is_empty()
+    }
+}
+
+impl StorageSink for MemorySink {
+    fn put(&self, key: &str, data: &[u8]) -> Result<(), Error> {
+        self.objects().insert(key.to_string(), data.to_vec());
+        Ok(())
+    }
+
+    fn append(&self, key: &str, data: &[u8]) -> Result<(), Error> {
+        self.objects()
+            .entry(key.to_string())
+            .or_default()
+            .extend_from_slice(data);
+        Ok(())
+    }
+
+    fn read(&self, key: &str) -> Result<Option<Vec<u8>>, Error> {
+        Ok(self.get(key))
+    }
+
+    fn exists(&self, key: &str) -> Result<bool, Error> {
+        Ok(self.objects().contains_key(key))
+    }
+
+    fn delete(&self, key: &str) -> Result<(), Error> {
+        self.objects().remove(key);
+        Ok(())
+    }
+
+    fn list(&self) -> Result<Vec<StoredObject>, Error> {
+        Ok(self
+            .objects()
+            .iter()
+            .map(|(key, data)| StoredObject {
+                key: key.clone(),
+                size: data.len() as u64,
+            })
+            .collect())
+    }
+
+    fn location(&self, key: &str) -> String {
+        format!("memory:{key}")
+    }
+}
@@ -0,0 +1,657 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use async_trait::async_trait;
+use bon::Builder;
+use flate2::{Compression, write::ZlibEncoder};
+use image::{
+    RgbImage,
+    imageops::{self, FilterType},
+};
+use remotia::{
+    buffers::BytesMut,
+    traits::{FrameError, FrameProcessor, FrameProperties, PullableFrameProperties},
+};
+
+use crate::{
+    error::Error,
+    geometry::Letterbox,
+    metadata::png_chunks::write_chunk,
+    pixel_format::{FrameFormat, PixelFormat},
+};
+
+pub const DEFAULT_TIMELAPSE_FRAME_RATE: u32 = 10;
+
+/// NeuQuant sampling factor, from 1 (best quality) to 30 (fastest)
+const GIF_QUANTIZATION_SPEED: i32 = 10;
+const GIF_TRAILER: u8 = 0x3b;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimelapseFormat {
+    /// Animated GIF, quantized to 256 colors per frame
+    #[default]
+    Gif,
+    /// Animated PNG
+    Apng,
+    /// Uncompressed YUV 4:4:4 video, for further encoding
+    Y4m,
+}
+
+impl TimelapseFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Gif => "gif",
+            Self::Apng => "png",
+            Self::Y4m => "y4m",
+        }
+    }
+}
+
+impl FromStr for TimelapseFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "gif" => Ok(Self::Gif),
+            "apng" | "png" => Ok(Self::Apng),
+            "y4m" => Ok(Self::Y4m),
+            _ => Err(format!("Unknown timelapse format: {value}")),
+        }
+    }
+}
+
+/// Open timelapse file. After each frame the file is left complete, its trailer
+/// being overwritten by the next frame, so that it stays playable if the process is killed.
+enum Encoder {
+    Gif(gif::Encoder<SkipHeader<BufWriter<File>>>),
+    Apng(ApngEncoder),
+    Y4m(BufWriter<File>),
+}
+
+impl Encoder {
+    /// Continues the timelapse of the path if there is one, unless told to overwrite it.
+    /// Timelapses which cannot be continued, e.g. of another size, are left untouched.
+    fn open(
+        format: TimelapseFormat,
+        path: &Path,
+        width: u32,
+        height: u32,
+        frame_rate: u32,
+        overwrite: bool,
+    ) -> Result<Self, Error> {
+        let exists = path.metadata().is_ok_and(|metadata| metadata.len() > 0);
+        if !exists || overwrite {
+            let file = File::create(path).map_err(|err| Error::WriteFailed.logged(err))?;
+            log::info!(
+                "Writing a {}x{} timelapse to {}",
+                width,
+                height,
+                path.display()
+            );
+
+            return Self::create(format, file, width, height, frame_rate);
+        }
+
+        OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)
+            .and_then(|file| Self::resume(format, file, width, height, frame_rate))
+            .inspect(|_| log::info!("Continuing the timelapse of {}", path.display()))
+            .map_err(|err| {
+                Error::WriteFailed.logged(format!(
+                    "unable to continue the timelapse of {}, refusing to overwrite it: {}",
+                    path.display(),
+                    err
+                ))
+            })
+    }
+
+    fn create(
+        format: TimelapseFormat,
+        file: File,
+        width: u32,
+        height: u32,
+        frame_rate: u32,
+    ) -> Result<Self, Error> {
+        let mut writer = BufWriter::new(file);
+
+        match format {
+            TimelapseFormat::Gif => {
+                let (gif_width, gif_height) = gif_size(width, height)?;
+
+                let mut encoder =
+                    gif::Encoder::new(SkipHeader::new(writer, 0), gif_width, gif_height, &[])
+                        .map_err(gif_error)?;
+                encoder
+                    .set_repeat(gif::Repeat::Infinite)
+                    .map_err(gif_error)?;
+
+                Ok(Self::Gif(encoder))
+            }
+            TimelapseFormat::Apng => ApngEncoder::new(writer, width, height, frame_rate)
+                .map(Self::Apng)
+                .map_err(|err| Error::WriteFailed.logged(err)),
+            TimelapseFormat::Y4m => {
+                writeln!(
+                    writer,
+                    "YUV4MPEG2 W{width} H{height} F{frame_rate}:1 Ip A1:1 C444"
+                )
+                .map_err(|err| Error::WriteFailed.logged(err))?;
+
+                Ok(Self::Y4m(writer))
+            }
+        }
+    }
+
+    /// Continues a complete timelapse of the same size, after its last frame
+    fn resume(
+        format: TimelapseFormat,
+        file: File,
+        width: u32,
+        height: u32,
+        frame_rate: u32,
+    ) -> io::Result<Self> {
+        match format {
+            TimelapseFormat::Gif => {
+                let (gif_width, gif_height) =
+                    gif_size(width, height).map_err(|_| invalid("frames too large for a GIF"))?;
+                resume_gif(file, gif_width, gif_height).map(Self::Gif)
+            }
+            TimelapseFormat::Apng => {
+                ApngEncoder::resume(BufWriter::new(file), width, height, frame_rate).map(Self::Apng)
+            }
+            TimelapseFormat::Y4m => resume_y4m(file, width, height).map(Self::Y4m),
+        }
+    }
+
+    /// Appends a packed RGB frame
+    fn append(
+        &mut self,
+        pixels: &[u8],
+        width: u32,
+        height: u32,
+        frame_rate: u32,
+    ) -> Result<(), Error> {
+        match self {
+            Self::Gif(encoder) => {
+                let mut frame = gif::Frame::from_rgb_speed(
+                    width as u16,
+                    height as u16,
+                    pixels,
+                    GIF_QUANTIZATION_SPEED,
+                );
+                // In hundredths of a second
+                frame.delay = (100 / frame_rate).clamp(1, u16::MAX as u32) as u16;
+
+                encoder.write_frame(&frame).map_err(gif_error)?;
+
+                let writer = encoder.get_mut();
+                writer
+                    .write_all(&[GIF_TRAILER])
+                    .and_then(|_| writer.seek(SeekFrom::Current(-1)))
+                    .map(|_| ())
+                    .map_err(|err| Error::WriteFailed.logged(err))
+            }
+            Self::Apng(encoder) => encoder
+                .append(pixels)
+                .map_err(|err| Error::WriteFailed.logged(err)),
+            Self::Y4m(writer) => writer
+                .write_all(b"FRAME\n")
+                .and_then(|_| writer.write_all(&yuv444_planes(pixels)))
+                .and_then(|_| writer.flush())
+                .map_err(|err| Error::WriteFailed.logged(err)),
+        }
+    }
+}
+
+fn gif_size(width: u32, height: u32) -> Result<(u16, u16), Error> {
+    match (u16::try_from(width), u16::try_from(height)) {
+        (Ok(width), Ok(height)) => Ok((width, height)),
+        _ => Err(Error::SizeMismatch.logged(format!(
+            "GIF frames cannot be larger than 65535x65535, got {width}x{height}"
+        ))),
+    }
+}
+
+fn invalid(reason: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason.into())
+}
+
+/// Writer dropping the first bytes written to it, to continue a file whose header is already written
+struct SkipHeader<W> {
+    inner: W,
+    remaining: usize,
+}
+
+impl<W> SkipHeader<W> {
+    fn new(inner: W, header_size: usize) -> Self {
+        Self {
+            inner,
+            remaining: header_size,
+        }
+    }
+}
+
+impl<W: Write> Write for SkipHeader<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let skipped = self.remaining.min(buf.len());
+        self.remaining -= skipped;
+        if skipped == buf.len() {
+            return Ok(skipped);
+        }
+
+        self.inner
+            .write(&buf[skipped..])
+            .map(|written| skipped + written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<W: Seek> Seek for SkipHeader<W> {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(position)
+    }
+}
+
+/// Continues a GIF ending with its trailer, which the next frame overwrites
+fn resume_gif(
+    mut file: File,
+    width: u16,
+    height: u16,
+) -> io::Result<gif::Encoder<SkipHeader<BufWriter<File>>>> {
+    let mut header = [0; 10];
+    file.read_exact(&mut header)?;
+    if &header[..6] != b"GIF89a"
+        || header[6..8] != width.to_le_bytes()
+        || header[8..10] != height.to_le_bytes()
+    {
+        return Err(invalid(format!("not a {width}x{height} GIF")));
+    }
+
+    let mut trailer = [0];
+    file.seek(SeekFrom::End(-1))?;
+    file.read_exact(&mut trailer)?;
+    if trailer[0] != GIF_TRAILER {
+        return Err(invalid("the GIF is truncated"));
+    }
+    file.seek(SeekFrom::End(-1))?;
+
+    // The encoder writes a header again, which the file already has
+    let header_size = gif::Encoder::new(Vec::new(), width, height, &[])
+        .map_err(io::Error::other)?
+        .get_ref()
+        .len();
+
+    gif::Encoder::new(
+        SkipHeader::new(BufWriter::new(file), header_size),
+        width,
+        height,
+        &[],
+    )
+    .map_err(io::Error::other)
+}
+
+/// Continues a Y4M video after its last complete frame, dropping any partially written one
+fn resume_y4m(mut file: File, width: u32, height: u32) -> io::Result<BufWriter<File>> {
+    let length = file.metadata()?.len();
+
+    let mut header = String::new();
+    BufReader::new(&mut file).take(256).read_line(&mut header)?;
+
+    let parameters: Vec<&str> = header.split_whitespace().collect();
+    let expected = [
+        "YUV4MPEG2".to_string(),
+        format!("W{width}"),
+        format!("H{height}"),
+        "C444".to_string(),
+    ];
+    if !header.ends_with('\n')
+        || !expected
+            .iter()
+            .all(|parameter| parameters.contains(&parameter.as_str()))
+    {
+        return Err(invalid(format!("not a {width}x{height} 4:4:4 Y4M video")));
+    }
+
+    let header_size = header.len() as u64;
+    let frame_size = b"FRAME\n".len() as u64 + width as u64 * height as u64 * 3;
+    let complete = header_size + (length - header_size) / frame_size * frame_size;
+    if complete < length {
+        log::warn!("Dropping the incomplete last frame of the timelapse");
+        file.set_len(complete)?;
+    }
+
+    file.seek(SeekFrom::Start(complete))?;
+    Ok(BufWriter::new(file))
+}
+
+fn gif_error(err: gif::EncodingError) -> Error {
+    match err {
+        gif::EncodingError::Io(err) => Error::WriteFailed.logged(err),
+        err => Error::EncodeFailed.logged(err),
+    }
+}
+
+/// Y, Cb and Cr planes of packed RGB pixels, using limited range BT.601
+fn yuv444_planes(pixels: &[u8]) -> Vec<u8> {
+    let count = pixels.len() / 3;
+    let mut planes = vec![0; count * 3];
+    let (luma, chroma) = planes.split_at_mut(count);
+    let (blue_difference, red_difference) = chroma.split_at_mut(count);
+
+    for (index, pixel) in pixels.chunks_exact(3).enumerate() {
+        let [r, g, b] = [pixel[0], pixel[1], pixel[2]].map(i32::from);
+
+        luma[index] = (((66 * r + 129 * g + 25 * b + 128) >> 8) + 16) as u8;
+        blue_difference[index] = (((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128) as u8;
+        red_difference[index] = (((112 * r - 94 * g - 18 * b + 128) >> 8) + 128) as u8;
+    }
+
+    planes
+}
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+/// Right after the signature and the IHDR chunk
+const ACTL_OFFSET: u64 = 8 + 12 + 13;
+const IEND_SIZE: i64 = 12;
+
+/// Animated PNG written one frame at a time, the frame count being updated after each
+struct ApngEncoder {
+    writer: BufWriter<File>,
+    width: u32,
+    height: u32,
+    frame_rate: u16,
+    frames: u32,
+    /// Shared by the fcTL and fdAT chunks
+    sequence: u32,
+}
+
+impl ApngEncoder {
+    fn new(
+        mut writer: BufWriter<File>,
+        width: u32,
+        height: u32,
+        frame_rate: u32,
+    ) -> io::Result<Self> {
+        let mut header = PNG_SIGNATURE.to_vec();
+
+        let mut ihdr = Vec::with_capacity(13);
+        ihdr.extend_from_slice(&width.to_be_bytes());
+        ihdr.extend_from_slice(&height.to_be_bytes());
+        // 8 bits truecolor, default compression and filtering, not interlaced
+        ihdr.extend_from_slice(&[8, 2, 0, 0, 0]);
+        write_chunk(&mut header, b"IHDR", &ihdr);
+        write_chunk(&mut header, b"acTL", &animation_control(0));
+
+        writer.write_all(&header)?;
+
+        Ok(Self {
+            writer,
+            width,
+            height,
+            frame_rate: frame_rate.clamp(1, u16::MAX as u32) as u16,
+            frames: 0,
+            sequence: 0,
+        })
+    }
+
+    /// Continues an animated PNG written by this encoder, checking that it is complete
+    fn resume(
+        mut writer: BufWriter<File>,
+        width: u32,
+        height: u32,
+        frame_rate: u32,
+    ) -> io::Result<Self> {
+        let file = writer.get_mut();
+
+        // Signature, IHDR and acTL chunks, as written when the timelapse was created
+        let mut header = [0; ACTL_OFFSET as usize + 20];
+        file.read_exact(&mut header)?;
+
+        let mut ihdr = width.to_be_bytes().to_vec();
+        ihdr.extend_from_slice(&height.to_be_bytes());
+        ihdr.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+        if !header.starts_with(PNG_SIGNATURE)
+            || &header[12..16] != b"IHDR"
+            || header[16..29] != ihdr
+            || &header[37..41] != b"acTL"
+        {
+            return Err(invalid(format!("not a {width}x{height} animated PNG")));
+        }
+
+        let frames = u32::from_be_bytes([header[41], header[42], header[43], header[44]]);
+
+        let mut end = [0; IEND_SIZE as usize];
+        file.seek(SeekFrom::End(-IEND_SIZE))?;
+        file.read_exact(&mut end)?;
+
+        let mut iend = Vec::new();
+        write_chunk(&mut iend, b"IEND", &[]);
+        if frames == 0 || end[..] != iend {
+            return Err(invalid("the animated PNG is truncated"));
+        }
+
+        writer.seek(SeekFrom::End(-IEND_SIZE))?;
+
+        Ok(Self {
+            writer,
+            width,
+            height,
+            frame_rate: frame_rate.clamp(1, u16::MAX as u32) as u16,
+            frames,
+            // One fcTL chunk per frame, and one fdAT chunk per frame but the first
+            sequence: frames * 2 - 1,
+        })
+    }
+
+    fn append(&mut self, pixels: &[u8]) -> io::Result<()> {
+        let mut frame_control = Vec::with_capacity(26);
+        frame_control.extend_from_slice(&self.sequence.to_be_bytes());
+        frame_control.extend_from_slice(&self.width.to_be_bytes());
+        frame_control.extend_from_slice(&self.height.to_be_bytes());
+        // Offset, delay of 1 / frame rate seconds, no disposal nor blending
+        frame_control.extend_from_slice(&[0; 8]);
+        frame_control.extend_from_slice(&1u16.to_be_bytes());
+        frame_control.extend_from_slice(&self.frame_rate.to_be_bytes());
+        frame_control.extend_from_slice(&[0, 0]);
+        self.sequence += 1;
+
+        let mut chunks = Vec::new();
+        write_chunk(&mut chunks, b"fcTL", &frame_control);
+
+        let data = compress(pixels, self.width as usize * 3)?;
+        if self.frames == 0 {
+            // The first frame doubles as the static image
+            write_chunk(&mut chunks, b"IDAT", &data);
+        } else {
+            let mut frame_data = self.sequence.to_be_bytes().to_vec();
+            frame_data.extend_from_slice(&data);
+            write_chunk(&mut chunks, b"fdAT", &frame_data);
+            self.sequence += 1;
+        }
+        write_chunk(&mut chunks, b"IEND", &[]);
+
+        self.writer.write_all(&chunks)?;
+        self.frames += 1;
+
+        let mut actl = Vec::new();
+        write_chunk(&mut actl, b"acTL", &animation_control(self.frames));
+
+        self.writer.seek(SeekFrom::Start(ACTL_OFFSET))?;
+        self.writer.write_all(&actl)?;
+        self.writer.seek(SeekFrom::End(-IEND_SIZE))?;
+
+        Ok(())
+    }
+}
+
+/// acTL chunk data, looping forever
+fn animation_control(frames: u32) -> [u8; 8] {
+    let mut data = [0; 8];
+    data[..4].copy_from_slice(&frames.to_be_bytes());
+    data
+}
+
+/// Zlib stream of the rows, unfiltered
+fn compress(pixels: &[u8], row_size: usize) -> io::Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    for row in pixels.chunks_exact(row_size) {
+        encoder.write_all(&[0])?;
+        encoder.write_all(row)?;
+    }
+
+    encoder.finish()
+}
+
+/// Appends each frame to an animated GIF, an animated PNG or a Y4M video
+#[derive(Builder)]
+pub struct TimelapseWriter<K> {
+    #[builder(skip)]
+    encoder: Option<Encoder>,
+
+    #[builder(skip)]
+    frames: usize,
+
+    /// Size of the first frame, which the timelapse keeps once started, as (width, height)
+    #[builder(skip)]
+    size: Option<(u32, u32)>,
+
+    /// Fits the frames of another size into the timelapse
+    #[builder(skip)]
+    letterbox: Option<Letterbox>,
+
+    buffer_key: K,
+
+    /// File the timelapse is written to, continued if it exists
+    #[builder(into)]
+    path: PathBuf,
+
+    /// Replace an existing timelapse instead of continuing it
+    #[builder(default)]
+    overwrite: bool,
+
+    #[builder(default)]
+    format: TimelapseFormat,
+
+    /// Playback frames per second
+    #[builder(default = DEFAULT_TIMELAPSE_FRAME_RATE)]
+    frame_rate: u32,
+
+    /// Divides the width and height of the frames, 1 keeping the captured size
+    #[builder(default = 1)]
+    downscale: u32,
+}
+
+impl<K> TimelapseWriter<K> {
+    /// Size of the timelapse frames, as (width, height)
+    fn output_size(&self, (width, height): (u32, u32)) -> (u32, u32) {
+        let factor = self.downscale.max(1);
+        ((width / factor).max(1), (height / factor).max(1))
+    }
+
+    /// Converts a frame to packed RGB at the size of the timelapse, letterboxing it if needed
+    fn fit(&mut self, format: FrameFormat, buffer: &[u8], size: (u32, u32)) -> Vec<u8> {
+        let (width, height) = (format.width, format.height);
+        let pixels = format.convert(buffer, PixelFormat::Rgb8);
+        if (width, height) == size {
+            return pixels;
+        }
+
+        let letterbox = match self.letterbox.take() {
+            Some(letterbox) if letterbox.maps(width, height, size.0, size.1) => letterbox,
+            _ => {
+                log::info!(
+                    "Letterboxing {}x{} frames into the {}x{} timelapse",
+                    width,
+                    height,
+                    size.0,
+                    size.1
+                );
+                Letterbox::new(width, height, size.0, size.1)
+            }
+        };
+
+        let packed = FrameFormat::packed(PixelFormat::Rgb8, width, height);
+        let pixels = letterbox.apply(packed, &pixels);
+        self.letterbox = Some(letterbox);
+        pixels
+    }
+
+    fn append(&mut self, format: FrameFormat, buffer: &[u8]) -> Result<(), Error> {
+        if !format.fits(buffer) {
+            return Err(Error::SizeMismatch.logged(format!(
+                "{} bytes do not match {:?}",
+                buffer.len(),
+                format
+            )));
+        }
+
+        let size = *self.size.get_or_insert((format.width, format.height));
+        let (width, height) = self.output_size(size);
+        let frame_rate = self.frame_rate.max(1);
+
+        let mut pixels = self.fit(format, buffer, size);
+        if (width, height) != size {
+            let image = RgbImage::from_raw(size.0, size.1, pixels).ok_or(Error::SizeMismatch)?;
+            pixels = imageops::resize(&image, width, height, FilterType::Triangle).into_raw();
+        }
+
+        let encoder = match &mut self.encoder {
+            Some(encoder) => encoder,
+            None => self.encoder.insert(Encoder::open(
+                self.format,
+                &self.path,
+                width,
+                height,
+                frame_rate,
+                self.overwrite,
+            )?),
+        };
+
+        encoder.append(&pixels, width, height, frame_rate)?;
+        self.frames += 1;
+
+        log::debug!("Appended frame {} to {}", self.frames, self.path.display());
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<K, F> FrameProcessor<F> for TimelapseWriter<K>
+where
+    F: Send + 'static,
+    K: Send + Copy,
+    F: PullableFrameProperties<K, BytesMut> + FrameProperties<K, FrameFormat> + FrameError<Error>,
+{
+    async fn process(&mut self, mut frame_data: F) -> Option<F> {
+        let Some(format) = FrameProperties::<K, FrameFormat>::get(&frame_data, &self.buffer_key)
+        else {
+            frame_data.report_error(Error::MissingFormat);
+            return Some(frame_data);
+        };
+
+        let Some(buffer) = frame_data.pull(&self.buffer_key) else {
+            frame_data.report_error(Error::MissingBuffer);
+            return Some(frame_data);
+        };
+
+        let result = self.append(format, &buffer);
+        frame_data.push(self.buffer_key, buffer);
+
+        if let Err(error) = result {
+            frame_data.report_error(error);
+        }
+
+        Some(frame_data)
+    }
+}
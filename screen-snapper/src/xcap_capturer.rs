@@ -1,38 +1,216 @@
 use async_trait::async_trait;
 use bon::Builder;
-use image::DynamicImage;
+use image::{DynamicImage, RgbaImage};
 use remotia::{
     buffers::{BufMut, BytesMut},
-    traits::{FrameProcessor, PullableFrameProperties},
+    traits::{FrameError, FrameProcessor, FrameProperties, PullableFrameProperties},
 };
 use xcap::Monitor;
 
+use crate::{
+    error::Error,
+    geometry::{GeometryChange, GeometryTracker},
+    layout::{DisplayLayout, MonitorGeometry},
+    monitor_selector::MonitorSelector,
+    pixel_format::{FrameFormat, PixelFormat},
+    region::Region,
+};
+
 #[derive(Builder)]
 pub struct XCapCapturer<K> {
+    #[builder(skip)]
+    handle: Option<Monitor>,
+
+    #[builder(skip)]
+    tracker: GeometryTracker,
+
     buffer_key: K,
-    monitor_id: usize,
+
+    #[builder(default)]
+    monitor: MonitorSelector,
+
+    region: Option<Region>,
+}
+
+// SAFETY: xcap monitors are identifiers rather than resources bound to a thread, each call
+// opening and releasing what it needs (X or D-Bus connections, device contexts):
+// - Linux: an RandR output XID
+// - macOS: a CGDirectDisplayID, a plain display number
+// - Windows: an HMONITOR, valid across the process and accepted by the monitor functions
+//   from any thread; only its raw pointer type keeps it from being Send
+unsafe impl<K: Send> Send for XCapCapturer<K> {}
+
+impl<K> XCapCapturer<K> {
+    pub fn monitor(&self) -> &MonitorSelector {
+        &self.monitor
+    }
+
+    pub fn region(&self) -> Option<Region> {
+        self.region
+    }
+
+    /// Geometry of the opened monitor
+    pub fn monitor_geometry(&self) -> Option<MonitorGeometry> {
+        xcap_utils::monitor_geometry(self.handle.as_ref()?).ok()
+    }
+
+    /// Top-left corner of the captured frames in desktop coordinates, accounting for the region
+    pub fn capture_origin(&self) -> Option<(i32, i32)> {
+        let geometry = self.monitor_geometry()?;
+        let (x, y) = self.region.map_or((0, 0), |region| (region.x, region.y));
+
+        Some((geometry.x + x as i32, geometry.y + y as i32))
+    }
+
+    /// Size of the captured frames, either the region's or the whole monitor's
+    pub fn capture_size(&self) -> Result<(u32, u32), Error> {
+        match self.region {
+            Some(region) => Ok((region.height, region.width)),
+            None => xcap_utils::display_size(&self.monitor),
+        }
+    }
+
+    /// Format of the captured frames, which are packed RGB
+    pub fn frame_format(&self) -> Result<FrameFormat, Error> {
+        let (height, width) = self.capture_size()?;
+        Ok(FrameFormat::packed(PixelFormat::Rgb8, width, height))
+    }
+
+    pub fn buffer_size(&self) -> Result<usize, Error> {
+        Ok(self.frame_format()?.buffer_size())
+    }
+
+    /// Opens the monitor handle ahead of the first capture, checking that the region fits in it.
+    /// A first frame of another format than the one opened is declared as a geometry change.
+    pub fn open(&mut self) -> Result<(), Error> {
+        if self.handle.is_none() {
+            self.handle = Some(xcap_utils::fetch_monitor(&self.monitor)?);
+        }
+
+        if let (Some(region), Some(monitor)) = (self.region, &self.handle) {
+            let (height, width) = xcap_utils::monitor_size(monitor)?;
+            if !region.fits(height, width) {
+                return Err(Error::RegionOutOfBounds.logged(format!(
+                    "{region} does not fit in monitor {} ({width}x{height})",
+                    self.monitor
+                )));
+            }
+        }
+
+        let format = self.frame_format()?;
+        self.tracker.announce(format);
+
+        Ok(())
+    }
+
+    fn capture(&mut self) -> Result<RgbaImage, Error> {
+        if let Some(monitor) = &self.handle {
+            match monitor.capture_image() {
+                Ok(rgba_image) => return Ok(rgba_image),
+                Err(err) => {
+                    log::warn!(
+                        "Capture failed ({err}), reopening monitor {}...",
+                        self.monitor
+                    );
+                    self.handle = None;
+                }
+            }
+        }
+
+        let monitor = xcap_utils::fetch_monitor(&self.monitor)?;
+        let rgba_image = monitor
+            .capture_image()
+            .map_err(|err| Error::CaptureFailed.logged(err))?;
+        self.handle = Some(monitor);
+
+        Ok(rgba_image)
+    }
 }
 
 pub mod xcap_utils {
     use super::*;
 
-    pub fn fetch_monitor_by_id(monitor_id: usize) -> Monitor {
-        Monitor::all()
-            .expect("Unable to fetch monitors")
-            .remove(monitor_id)
+    pub fn fetch_monitor_by_id(monitor_id: usize) -> Result<Monitor, Error> {
+        let mut monitors = Monitor::all().map_err(|err| Error::BackendUnavailable.logged(err))?;
+
+        if monitor_id >= monitors.len() {
+            return Err(Error::MonitorNotFound.logged(format!(
+                "no monitor with ID {monitor_id}, {} available",
+                monitors.len()
+            )));
+        }
+
+        Ok(monitors.remove(monitor_id))
     }
 
-    pub fn display_size(monitor_id: usize) -> (u32, u32) {
-        let monitor = fetch_monitor_by_id(monitor_id);
-        (
-            monitor.height().expect("Unable to fetch monitor's height"),
-            monitor.width().expect("Unable to fetch monitor's width"),
-        )
+    pub fn fetch_monitor(selector: &MonitorSelector) -> Result<Monitor, Error> {
+        let monitors = Monitor::all().map_err(|err| Error::BackendUnavailable.logged(err))?;
+        for (index, monitor) in monitors.into_iter().enumerate() {
+            let found = match selector {
+                MonitorSelector::Primary => monitor.is_primary().unwrap_or(false),
+                MonitorSelector::Name(name) => monitor
+                    .name()
+                    .is_ok_and(|value| value.eq_ignore_ascii_case(name)),
+                MonitorSelector::Point(x, y) => {
+                    let geometry = monitor_geometry(&monitor)?;
+                    (geometry.x..geometry.x + geometry.width as i32).contains(x)
+                        && (geometry.y..geometry.y + geometry.height as i32).contains(y)
+                }
+                MonitorSelector::Index(monitor_id) => index == *monitor_id,
+            };
+
+            if found {
+                return Ok(monitor);
+            }
+        }
+
+        Err(Error::MonitorNotFound.logged(format!("no monitor matching {selector}")))
+    }
+
+    pub fn display_size(selector: &MonitorSelector) -> Result<(u32, u32), Error> {
+        monitor_size(&fetch_monitor(selector)?)
     }
 
-    pub fn expected_buffer_size_for_monitor(monitor_id: usize) -> usize {
-        let (height, width) = display_size(monitor_id);
-        height as usize * width as usize * 3
+    pub fn monitor_size(monitor: &Monitor) -> Result<(u32, u32), Error> {
+        Ok((
+            monitor
+                .height()
+                .map_err(|err| Error::BackendUnavailable.logged(err))?,
+            monitor
+                .width()
+                .map_err(|err| Error::BackendUnavailable.logged(err))?,
+        ))
+    }
+
+    pub fn monitor_geometry(monitor: &Monitor) -> Result<MonitorGeometry, Error> {
+        let (height, width) = monitor_size(monitor)?;
+        Ok(MonitorGeometry {
+            name: monitor
+                .name()
+                .map_err(|err| Error::BackendUnavailable.logged(err))?,
+            x: monitor
+                .x()
+                .map_err(|err| Error::BackendUnavailable.logged(err))?,
+            y: monitor
+                .y()
+                .map_err(|err| Error::BackendUnavailable.logged(err))?,
+            width,
+            height,
+        })
+    }
+
+    pub fn display_layout(monitors: &[Monitor]) -> Result<DisplayLayout, Error> {
+        Ok(DisplayLayout {
+            monitors: monitors
+                .iter()
+                .map(monitor_geometry)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    pub fn expected_buffer_size_for_monitor(selector: &MonitorSelector) -> Result<usize, Error> {
+        let (height, width) = display_size(selector)?;
+        Ok(FrameFormat::packed(PixelFormat::Rgb8, width, height).buffer_size())
     }
 }
 
@@ -41,14 +219,25 @@ impl<K, F> FrameProcessor<F> for XCapCapturer<K>
 where
     F: Send + 'static,
     K: Send + Copy,
-    F: PullableFrameProperties<K, BytesMut>,
+    F: PullableFrameProperties<K, BytesMut>
+        + FrameProperties<K, FrameFormat>
+        + FrameProperties<K, GeometryChange>
+        + FrameError<Error>,
 {
     async fn process(&mut self, mut dto: F) -> Option<F> {
         // Capture screen data
         log::debug!("Capturing screen data...");
-        let rgba_image = xcap_utils::fetch_monitor_by_id(self.monitor_id)
-            .capture_image()
-            .expect("Unable to capture screen buffer to image");
+        let captured = self.capture().and_then(|rgba_image| match self.region {
+            Some(region) => region.crop(&rgba_image),
+            None => Ok(rgba_image),
+        });
+        let rgba_image = match captured {
+            Ok(rgba_image) => rgba_image,
+            Err(error) => {
+                dto.report_error(error);
+                return Some(dto);
+            }
+        };
 
         // Remove the alpha channel
         log::debug!("Removing alpha channel...");
@@ -56,15 +245,23 @@ where
 
         // Write data into the DTO buffer
         log::debug!("Writing data to DTO...");
-        let mut buffer = dto
-            .pull(&self.buffer_key)
-            .expect("No buffer to pull from frame data");
+        let Some(mut buffer) = dto.pull(&self.buffer_key) else {
+            dto.report_error(Error::MissingBuffer);
+            return Some(dto);
+        };
 
         buffer.clear();
         buffer.put_slice(rgb_image.as_raw());
 
         dto.push(self.buffer_key, buffer);
 
+        // The monitor may have changed resolution, or been replaced by another one when reopened
+        self.tracker.declare(
+            &mut dto,
+            self.buffer_key,
+            FrameFormat::packed(PixelFormat::Rgb8, rgb_image.width(), rgb_image.height()),
+        );
+
         // Return the filled DTO
         log::debug!("Done");
         Some(dto)
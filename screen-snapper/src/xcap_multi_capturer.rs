@@ -0,0 +1,223 @@
+use async_trait::async_trait;
+use bon::Builder;
+use image::{DynamicImage, RgbaImage};
+use remotia::{
+    buffers::{BufMut, BytesMut},
+    traits::{FrameError, FrameProcessor, FrameProperties, PullableFrameProperties},
+};
+use xcap::Monitor;
+
+use crate::{
+    error::Error,
+    geometry::{GeometryChange, GeometryTracker},
+    layout::{DisplayLayout, MonitorGeometry, MultiMonitorMode},
+    pixel_format::{FrameFormat, PixelFormat},
+    xcap_capturer::xcap_utils,
+};
+
+#[derive(Builder)]
+pub struct XCapMultiCapturer<K> {
+    #[builder(skip)]
+    monitors: Vec<Monitor>,
+
+    /// Layout of the monitors as of the last capture
+    #[builder(skip)]
+    layout: DisplayLayout,
+
+    /// One per buffer written
+    #[builder(skip)]
+    trackers: Vec<GeometryTracker>,
+
+    mode: MultiMonitorMode<K>,
+}
+
+// SAFETY: xcap monitors are identifiers rather than resources bound to a thread, each call
+// opening and releasing what it needs (X or D-Bus connections, device contexts):
+// - Linux: an RandR output XID
+// - macOS: a CGDirectDisplayID, a plain display number
+// - Windows: an HMONITOR, valid across the process and accepted by the monitor functions
+//   from any thread; only its raw pointer type keeps it from being Send
+unsafe impl<K: Send> Send for XCapMultiCapturer<K> {}
+
+impl<K> XCapMultiCapturer<K> {
+    pub fn mode(&self) -> &MultiMonitorMode<K> {
+        &self.mode
+    }
+
+    /// Layout the buffers are sized after, known once the capturer is opened
+    /// and updated on each capture
+    pub fn layout(&self) -> &DisplayLayout {
+        &self.layout
+    }
+
+    /// Opens the monitor handles ahead of the first capture and records their layout
+    pub fn open(&mut self) -> Result<(), Error> {
+        if self.monitors.is_empty() {
+            self.refresh()?;
+            self.announce();
+        }
+
+        Ok(())
+    }
+
+    /// Fetches the monitors again, as they may have been plugged, unplugged or resized
+    fn refresh(&mut self) -> Result<(), Error> {
+        let monitors = self.fetch_monitors()?;
+        let layout = xcap_utils::display_layout(&monitors)?;
+
+        if !self.layout.monitors.is_empty() && layout != self.layout {
+            log::info!("Monitor layout changed to {:?}", layout.monitors);
+        }
+
+        self.monitors = monitors;
+        self.layout = layout;
+
+        Ok(())
+    }
+
+    /// Announces the formats of the layout as opened, so that a first capture of another
+    /// layout is declared as a geometry change
+    fn announce(&mut self) {
+        let formats: Vec<_> = match &self.mode {
+            MultiMonitorMode::Stitched(_) => vec![self.layout.canvas_format()],
+            MultiMonitorMode::PerMonitor(_) => self
+                .layout
+                .monitors
+                .iter()
+                .map(MonitorGeometry::frame_format)
+                .collect(),
+        };
+
+        self.trackers = formats
+            .into_iter()
+            .map(|format| {
+                let mut tracker = GeometryTracker::default();
+                tracker.announce(format);
+                tracker
+            })
+            .collect();
+    }
+
+    fn fetch_monitors(&self) -> Result<Vec<Monitor>, Error> {
+        let mut monitors = Monitor::all().map_err(|err| Error::BackendUnavailable.logged(err))?;
+
+        if let MultiMonitorMode::PerMonitor(keys) = &self.mode {
+            if keys.len() > monitors.len() {
+                return Err(Error::MonitorNotFound.logged(format!(
+                    "{} buffers for {} monitors",
+                    keys.len(),
+                    monitors.len()
+                )));
+            }
+
+            monitors.truncate(keys.len());
+        }
+
+        if monitors.is_empty() {
+            return Err(Error::MonitorNotFound.logged("no monitors available"));
+        }
+
+        Ok(monitors)
+    }
+
+    fn capture(&mut self) -> Result<Vec<RgbaImage>, Error> {
+        self.refresh()?;
+        capture_monitors(&self.monitors)
+    }
+
+    fn write<F>(&mut self, dto: &mut F, rgba_images: Vec<RgbaImage>) -> Result<(), Error>
+    where
+        K: Copy,
+        F: PullableFrameProperties<K, BytesMut>
+            + FrameProperties<K, FrameFormat>
+            + FrameProperties<K, GeometryChange>,
+    {
+        match &self.mode {
+            MultiMonitorMode::Stitched(buffer_key) => {
+                self.trackers.resize_with(1, Default::default);
+
+                let canvas = self.layout.stitch(&rgba_images)?;
+                write_buffer(dto, *buffer_key, &mut self.trackers[0], canvas)
+            }
+            MultiMonitorMode::PerMonitor(buffer_keys) => {
+                self.trackers
+                    .resize_with(buffer_keys.len(), Default::default);
+
+                for (index, (buffer_key, rgba_image)) in
+                    buffer_keys.iter().zip(rgba_images).enumerate()
+                {
+                    self.layout.check(index, &rgba_image)?;
+                    write_buffer(dto, *buffer_key, &mut self.trackers[index], rgba_image)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+fn capture_monitors(monitors: &[Monitor]) -> Result<Vec<RgbaImage>, Error> {
+    monitors
+        .iter()
+        .map(|monitor| {
+            monitor
+                .capture_image()
+                .map_err(|err| Error::CaptureFailed.logged(err))
+        })
+        .collect()
+}
+
+fn write_buffer<K, F>(
+    dto: &mut F,
+    buffer_key: K,
+    tracker: &mut GeometryTracker,
+    rgba_image: RgbaImage,
+) -> Result<(), Error>
+where
+    K: Copy,
+    F: PullableFrameProperties<K, BytesMut>
+        + FrameProperties<K, FrameFormat>
+        + FrameProperties<K, GeometryChange>,
+{
+    // Remove the alpha channel
+    let rgb_image = DynamicImage::ImageRgba8(rgba_image).into_rgb8();
+
+    let mut buffer = dto.pull(&buffer_key).ok_or(Error::MissingBuffer)?;
+    buffer.clear();
+    buffer.put_slice(rgb_image.as_raw());
+    dto.push(buffer_key, buffer);
+    tracker.declare(
+        dto,
+        buffer_key,
+        FrameFormat::packed(PixelFormat::Rgb8, rgb_image.width(), rgb_image.height()),
+    );
+
+    Ok(())
+}
+
+#[async_trait]
+impl<K, F> FrameProcessor<F> for XCapMultiCapturer<K>
+where
+    F: Send + 'static,
+    K: Send + Copy,
+    F: PullableFrameProperties<K, BytesMut>
+        + FrameProperties<K, FrameFormat>
+        + FrameProperties<K, GeometryChange>
+        + FrameError<Error>,
+{
+    async fn process(&mut self, mut dto: F) -> Option<F> {
+        // Capture screen data
+        log::debug!("Capturing {} monitors...", self.layout.monitors.len());
+        let result = self
+            .capture()
+            .and_then(|rgba_images| self.write(&mut dto, rgba_images));
+
+        if let Err(error) = result {
+            dto.report_error(error);
+        }
+
+        // Return the filled DTO
+        log::debug!("Done");
+        Some(dto)
+    }
+}
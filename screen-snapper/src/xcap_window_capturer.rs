@@ -0,0 +1,341 @@
+use std::{fmt::Display, str::FromStr};
+
+use async_trait::async_trait;
+use bon::Builder;
+use image::{
+    DynamicImage, RgbaImage,
+    imageops::{self, FilterType},
+};
+use remotia::{
+    buffers::{BufMut, BytesMut},
+    traits::{FrameError, FrameProcessor, FrameProperties, PullableFrameProperties},
+};
+use xcap::Window;
+
+use crate::{
+    error::Error,
+    geometry::{GeometryChange, GeometryTracker},
+    pixel_format::{FrameFormat, PixelFormat},
+    redaction::{AreaLocator, RedactionArea},
+};
+
+/// Picks the window to capture among the ones listed by xcap
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WindowSelector {
+    /// Substring of the window title
+    Title(String),
+    /// Application name, compared case-insensitively
+    AppName(String),
+    Id(u32),
+}
+
+impl WindowSelector {
+    fn matches(&self, window: &Window) -> bool {
+        match self {
+            Self::Title(title) => window
+                .title()
+                .is_ok_and(|value| value.contains(title.as_str())),
+            Self::AppName(app_name) => window
+                .app_name()
+                .is_ok_and(|value| value.eq_ignore_ascii_case(app_name)),
+            Self::Id(id) => window.id().is_ok_and(|value| value == *id),
+        }
+    }
+}
+
+impl Display for WindowSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Title(title) => write!(f, "title:{title}"),
+            Self::AppName(app_name) => write!(f, "app:{app_name}"),
+            Self::Id(id) => write!(f, "id:{id}"),
+        }
+    }
+}
+
+/// Parses "title:<substring>", "app:<name>" or "id:<window id>"
+impl FromStr for WindowSelector {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.split_once(':') {
+            Some(("title", title)) => Ok(Self::Title(title.to_string())),
+            Some(("app", app_name)) => Ok(Self::AppName(app_name.to_string())),
+            Some(("id", id)) => id
+                .parse()
+                .map(Self::Id)
+                .map_err(|_| format!("Invalid window ID: {id}")),
+            _ => Err(format!(
+                "Invalid window selector (expected title:, app: or id:): {value}"
+            )),
+        }
+    }
+}
+
+/// What to do with frames captured after the window has been resized
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResizePolicy {
+    /// Drop the frame, reporting a size mismatch
+    #[default]
+    Reject,
+    /// Scale the frame back to the size the window had when first captured
+    Scale,
+    /// Keep the new size, signalling the change to the processors downstream
+    Follow,
+}
+
+impl FromStr for ResizePolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "reject" => Ok(Self::Reject),
+            "scale" => Ok(Self::Scale),
+            "follow" => Ok(Self::Follow),
+            _ => Err(format!("Unknown resize policy: {value}")),
+        }
+    }
+}
+
+#[derive(Builder)]
+pub struct XCapWindowCapturer<K> {
+    #[builder(skip)]
+    window_handle: Option<Window>,
+
+    /// Output size as (height, width), locked on open or on the first capture
+    /// unless following the window's size
+    #[builder(skip)]
+    size: Option<(u32, u32)>,
+
+    #[builder(skip)]
+    tracker: GeometryTracker,
+
+    buffer_key: K,
+    window: WindowSelector,
+
+    #[builder(default)]
+    on_resize: ResizePolicy,
+}
+
+// SAFETY: xcap windows are identifiers rather than resources bound to a thread, each call
+// opening and releasing what it needs (X connections, device contexts):
+// - Linux: an X window XID
+// - macOS: a CGWindowID, which xcap already declares Send
+// - Windows: an HWND, valid across the process and accepted by the capture functions from
+//   any thread; xcap does not list the windows of this process, whose message loop could
+//   otherwise wait on a capture. Only the raw pointer type of HWND keeps it from being Send.
+unsafe impl<K: Send> Send for XCapWindowCapturer<K> {}
+
+impl<K> XCapWindowCapturer<K> {
+    pub fn window(&self) -> &WindowSelector {
+        &self.window
+    }
+
+    /// Size of the captured frames: the window's size when the capturer was opened,
+    /// or its latest one when following its resizes
+    pub fn capture_size(&self) -> Result<(u32, u32), Error> {
+        match self.size {
+            Some(size) => Ok(size),
+            None => xcap_window_utils::window_size(&xcap_window_utils::find_window(&self.window)?),
+        }
+    }
+
+    /// Format of the captured frames, which are packed RGB
+    pub fn frame_format(&self) -> Result<FrameFormat, Error> {
+        let (height, width) = self.capture_size()?;
+        Ok(FrameFormat::packed(PixelFormat::Rgb8, width, height))
+    }
+
+    pub fn buffer_size(&self) -> Result<usize, Error> {
+        Ok(self.frame_format()?.buffer_size())
+    }
+
+    /// Looks up the window ahead of the first capture and locks the output size.
+    /// A first frame of another format than the one opened is declared as a geometry change.
+    pub fn open(&mut self) -> Result<(), Error> {
+        if self.window_handle.is_none() {
+            self.window_handle = Some(xcap_window_utils::find_window(&self.window)?);
+        }
+
+        if let (None, Some(window)) = (self.size, &self.window_handle) {
+            self.size = Some(xcap_window_utils::window_size(window)?);
+        }
+
+        let format = self.frame_format()?;
+        self.tracker.announce(format);
+
+        Ok(())
+    }
+
+    fn capture(&mut self) -> Result<RgbaImage, Error> {
+        if let Some(window) = &self.window_handle {
+            match capture_window(window) {
+                Ok(rgba_image) => return Ok(rgba_image),
+                Err(_) => {
+                    log::warn!("Capture failed, looking up window {} again...", self.window);
+                    self.window_handle = None;
+                }
+            }
+        }
+
+        let window = xcap_window_utils::find_window(&self.window)?;
+        let rgba_image = capture_window(&window)?;
+        self.window_handle = Some(window);
+
+        Ok(rgba_image)
+    }
+
+    fn fit(&mut self, rgba_image: RgbaImage) -> Result<RgbaImage, Error> {
+        let (height, width) = *self
+            .size
+            .get_or_insert((rgba_image.height(), rgba_image.width()));
+
+        if rgba_image.dimensions() == (width, height) {
+            return Ok(rgba_image);
+        }
+
+        match self.on_resize {
+            ResizePolicy::Reject => Err(Error::SizeMismatch.logged(format!(
+                "window {} resized from {}x{} to {}x{}",
+                self.window,
+                width,
+                height,
+                rgba_image.width(),
+                rgba_image.height()
+            ))),
+            ResizePolicy::Scale => Ok(imageops::resize(
+                &rgba_image,
+                width,
+                height,
+                FilterType::Triangle,
+            )),
+            ResizePolicy::Follow => {
+                self.size = Some((rgba_image.height(), rgba_image.width()));
+                Ok(rgba_image)
+            }
+        }
+    }
+}
+
+fn capture_window(window: &Window) -> Result<RgbaImage, Error> {
+    if window.is_minimized().unwrap_or(false) {
+        return Err(Error::CaptureFailed.logged("window is minimized"));
+    }
+
+    window
+        .capture_image()
+        .map_err(|err| Error::CaptureFailed.logged(err))
+}
+
+pub mod xcap_window_utils {
+    use super::*;
+
+    pub fn find_window(selector: &WindowSelector) -> Result<Window, Error> {
+        Window::all()
+            .map_err(|err| Error::BackendUnavailable.logged(err))?
+            .into_iter()
+            .find(|window| selector.matches(window))
+            .ok_or_else(|| Error::WindowNotFound.logged(format!("no window matching {selector}")))
+    }
+
+    pub fn window_size(window: &Window) -> Result<(u32, u32), Error> {
+        Ok((
+            window
+                .height()
+                .map_err(|err| Error::BackendUnavailable.logged(err))?,
+            window
+                .width()
+                .map_err(|err| Error::BackendUnavailable.logged(err))?,
+        ))
+    }
+}
+
+/// Locates the visible windows matching any of the selectors, for them to be redacted
+pub struct XCapWindowLocator {
+    selectors: Vec<WindowSelector>,
+
+    /// Top-left corner of the captured surface, in desktop coordinates
+    origin: (i32, i32),
+}
+
+impl XCapWindowLocator {
+    pub fn new(selectors: Vec<WindowSelector>, origin: (i32, i32)) -> Self {
+        Self { selectors, origin }
+    }
+
+    fn area(&self, window: &Window) -> Result<RedactionArea, Error> {
+        let backend_error = |err| Error::BackendUnavailable.logged(err);
+        let (origin_x, origin_y) = self.origin;
+
+        Ok(RedactionArea {
+            x: window.x().map_err(backend_error)? - origin_x,
+            y: window.y().map_err(backend_error)? - origin_y,
+            width: window.width().map_err(backend_error)?,
+            height: window.height().map_err(backend_error)?,
+        })
+    }
+}
+
+impl AreaLocator for XCapWindowLocator {
+    fn locate(&mut self) -> Result<Vec<RedactionArea>, Error> {
+        Window::all()
+            .map_err(|err| Error::BackendUnavailable.logged(err))?
+            .iter()
+            .filter(|window| {
+                self.selectors
+                    .iter()
+                    .any(|selector| selector.matches(window))
+            })
+            .filter(|window| !window.is_minimized().unwrap_or(false))
+            .map(|window| self.area(window))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl<K, F> FrameProcessor<F> for XCapWindowCapturer<K>
+where
+    F: Send + 'static,
+    K: Send + Copy,
+    F: PullableFrameProperties<K, BytesMut>
+        + FrameProperties<K, FrameFormat>
+        + FrameProperties<K, GeometryChange>
+        + FrameError<Error>,
+{
+    async fn process(&mut self, mut dto: F) -> Option<F> {
+        // Capture window data
+        log::debug!("Capturing window data...");
+        let rgba_image = match self.capture().and_then(|rgba_image| self.fit(rgba_image)) {
+            Ok(rgba_image) => rgba_image,
+            Err(error) => {
+                dto.report_error(error);
+                return Some(dto);
+            }
+        };
+
+        // Remove the alpha channel
+        log::debug!("Removing alpha channel...");
+        let rgb_image = DynamicImage::ImageRgba8(rgba_image).into_rgb8();
+
+        // Write data into the DTO buffer
+        log::debug!("Writing data to DTO...");
+        let Some(mut buffer) = dto.pull(&self.buffer_key) else {
+            dto.report_error(Error::MissingBuffer);
+            return Some(dto);
+        };
+
+        buffer.clear();
+        buffer.put_slice(rgb_image.as_raw());
+
+        dto.push(self.buffer_key, buffer);
+        self.tracker.declare(
+            &mut dto,
+            self.buffer_key,
+            FrameFormat::packed(PixelFormat::Rgb8, rgb_image.width(), rgb_image.height()),
+        );
+
+        // Return the filled DTO
+        log::debug!("Done");
+        Some(dto)
+    }
+}
@@ -0,0 +1,89 @@
+#![allow(dead_code)]
+
+use remotia::{
+    buffers::BytesMut,
+    traits::{BorrowMutFrameProperties, FrameError, FrameProperties, PullableFrameProperties},
+};
+use screen_snapper::{
+    change_detector::ChangeScore, error::Error, geometry::GeometryChange, pixel_format::FrameFormat,
+};
+
+/// Frame data holding a single buffer, keyed by `()`
+#[derive(Default, Debug)]
+pub struct TestFrame {
+    pub buffer: Option<BytesMut>,
+    pub format: Option<FrameFormat>,
+    pub geometry_change: Option<GeometryChange>,
+    pub change_score: Option<ChangeScore>,
+    pub error: Option<Error>,
+}
+
+impl TestFrame {
+    pub fn new(format: FrameFormat, pixels: &[u8]) -> Self {
+        Self {
+            buffer: Some(BytesMut::from(pixels)),
+            format: Some(format),
+            ..Default::default()
+        }
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        self.buffer.as_deref().unwrap_or_default()
+    }
+}
+
+impl BorrowMutFrameProperties<(), BytesMut> for TestFrame {
+    fn get_mut_ref(&mut self, _: &()) -> Option<&mut BytesMut> {
+        self.buffer.as_mut()
+    }
+}
+
+impl PullableFrameProperties<(), BytesMut> for TestFrame {
+    fn push(&mut self, _: (), value: BytesMut) {
+        self.buffer = Some(value);
+    }
+
+    fn pull(&mut self, _: &()) -> Option<BytesMut> {
+        self.buffer.take()
+    }
+}
+
+impl FrameProperties<(), FrameFormat> for TestFrame {
+    fn set(&mut self, _: (), value: FrameFormat) {
+        self.format = Some(value);
+    }
+
+    fn get(&self, _: &()) -> Option<FrameFormat> {
+        self.format
+    }
+}
+
+impl FrameProperties<(), GeometryChange> for TestFrame {
+    fn set(&mut self, _: (), value: GeometryChange) {
+        self.geometry_change = Some(value);
+    }
+
+    fn get(&self, _: &()) -> Option<GeometryChange> {
+        self.geometry_change
+    }
+}
+
+impl FrameProperties<(), ChangeScore> for TestFrame {
+    fn set(&mut self, _: (), value: ChangeScore) {
+        self.change_score = Some(value);
+    }
+
+    fn get(&self, _: &()) -> Option<ChangeScore> {
+        self.change_score
+    }
+}
+
+impl FrameError<Error> for TestFrame {
+    fn report_error(&mut self, error: Error) {
+        self.error = Some(error);
+    }
+
+    fn get_error(&self) -> Option<Error> {
+        self.error
+    }
+}
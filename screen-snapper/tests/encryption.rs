@@ -0,0 +1,152 @@
+use std::{fs, path::PathBuf, time::Instant};
+
+use screen_snapper::{
+    encryption::{EncryptionKey, EnvelopeHeader, Keyring},
+    error::Error,
+};
+
+const SNAPSHOT: &[u8] = b"\x89PNG\r\n\x1a\nnot quite a snapshot";
+
+/// Offset of the Argon2 iteration count in the header of a passphrase-encrypted snapshot
+const ITERATIONS_OFFSET: usize = 8 + 1 + 1 + 1 + 4;
+
+fn key_file(name: &str) -> PathBuf {
+    let path =
+        std::env::temp_dir().join(format!("screen-snapper-{name}-{}.key", std::process::id()));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+fn keyring(keys: impl IntoIterator<Item = EncryptionKey>) -> Keyring {
+    let mut keyring = Keyring::new();
+    for key in keys {
+        keyring.add_key(key);
+    }
+    keyring
+}
+
+#[test]
+fn key_file_round_trip() {
+    let path = key_file("round-trip");
+    let key = EncryptionKey::generate_key_file(&path).unwrap();
+    let encrypted = key.encrypt(SNAPSHOT).unwrap();
+
+    // Read back from the file, as snapcrypt does
+    let read = EncryptionKey::from_key_file(&path).unwrap();
+    assert_eq!(read.id(), key.id());
+    assert_eq!(keyring([read]).decrypt(&encrypted).unwrap(), SNAPSHOT);
+
+    // A new nonce for each snapshot
+    assert_ne!(key.encrypt(SNAPSHOT).unwrap(), encrypted);
+
+    // Another key file is refused rather than decrypting garbage
+    let other = key_file("round-trip-other");
+    let other_key = EncryptionKey::generate_key_file(&other).unwrap();
+    assert_eq!(
+        keyring([other_key]).decrypt(&encrypted),
+        Err(Error::DecryptFailed)
+    );
+
+    // Existing key files are not replaced
+    assert_eq!(
+        EncryptionKey::generate_key_file(&path).err(),
+        Some(Error::InvalidKey)
+    );
+
+    fs::remove_file(path).unwrap();
+    fs::remove_file(other).unwrap();
+}
+
+#[test]
+fn passphrase_round_trip() {
+    let key = EncryptionKey::from_passphrase("correct horse").unwrap();
+    let encrypted = key.encrypt(SNAPSHOT).unwrap();
+
+    let (header, _) = EnvelopeHeader::parse(&encrypted).unwrap();
+    assert_eq!(header.key_id, key.id());
+    assert!(header.kdf.is_some());
+
+    let mut wrong = Keyring::new();
+    wrong.add_passphrase("battery staple");
+    assert_eq!(wrong.decrypt(&encrypted), Err(Error::DecryptFailed));
+
+    let mut right = Keyring::new();
+    right.add_passphrase("correct horse");
+    assert_eq!(right.decrypt(&encrypted).unwrap(), SNAPSHOT);
+}
+
+#[test]
+fn tampering_is_detected() {
+    let path = key_file("tampering");
+    let key = EncryptionKey::generate_key_file(&path).unwrap();
+    let encrypted = key.encrypt(SNAPSHOT).unwrap();
+    let (_, length) = EnvelopeHeader::parse(&encrypted).unwrap();
+    let mut keyring = keyring([key]);
+
+    // The nonce ends the header, which is authenticated along with the ciphertext
+    let mut header = encrypted.clone();
+    header[length - 1] ^= 1;
+    assert_eq!(keyring.decrypt(&header), Err(Error::DecryptFailed));
+
+    let mut ciphertext = encrypted.clone();
+    ciphertext[length + 3] ^= 1;
+    assert_eq!(keyring.decrypt(&ciphertext), Err(Error::DecryptFailed));
+
+    let mut magic = encrypted.clone();
+    magic[0] = b'X';
+    assert_eq!(keyring.decrypt(&magic), Err(Error::DecryptFailed));
+
+    assert_eq!(
+        keyring.decrypt(&encrypted[..length - 4]),
+        Err(Error::DecryptFailed)
+    );
+    assert_eq!(keyring.decrypt(&encrypted).unwrap(), SNAPSHOT);
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn rotated_keys_stay_readable() {
+    let path = key_file("rotation");
+    let old_key = EncryptionKey::generate_key_file(&path).unwrap();
+    let old_snapshot = old_key.encrypt(b"before the rotation").unwrap();
+
+    let new_key = EncryptionKey::from_passphrase("rotated").unwrap();
+    let new_snapshot = new_key.encrypt(b"after the rotation").unwrap();
+
+    let mut keyring = keyring([EncryptionKey::from_key_file(&path).unwrap()]);
+    keyring.add_passphrase("rotated");
+
+    assert_eq!(
+        keyring.decrypt(&old_snapshot).unwrap(),
+        b"before the rotation"
+    );
+    assert_eq!(
+        keyring.decrypt(&new_snapshot).unwrap(),
+        b"after the rotation"
+    );
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn forged_key_derivation_costs_are_refused() {
+    let key = EncryptionKey::from_passphrase("costly").unwrap();
+    let encrypted = key.encrypt(SNAPSHOT).unwrap();
+
+    for offset in [
+        ITERATIONS_OFFSET - 4,
+        ITERATIONS_OFFSET,
+        ITERATIONS_OFFSET + 4,
+    ] {
+        let mut forged = encrypted.clone();
+        forged[offset..offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        let mut keyring = Keyring::new();
+        keyring.add_passphrase("costly");
+
+        let started = Instant::now();
+        assert_eq!(keyring.decrypt(&forged), Err(Error::InvalidKey));
+        assert!(started.elapsed().as_secs() < 1);
+    }
+}
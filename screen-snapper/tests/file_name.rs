@@ -0,0 +1,96 @@
+use chrono::{Local, TimeZone};
+use screen_snapper::file_name::{FileNameContext, FileNameTemplate};
+
+fn context(id: usize) -> FileNameContext<'static> {
+    FileNameContext {
+        id,
+        time: Local.with_ymd_and_hms(2024, 3, 9, 14, 5, 7).unwrap(),
+        monitor: Some("DP-1"),
+        hostname: "desk",
+        width: 1920,
+        height: 1080,
+    }
+}
+
+fn render(template: &str, context: &FileNameContext) -> String {
+    template
+        .parse::<FileNameTemplate>()
+        .unwrap()
+        .render(context)
+}
+
+#[test]
+fn pads_the_sequence_number() {
+    assert_eq!(render("{id}", &context(7)), "7");
+    assert_eq!(render("{id:4}", &context(7)), "0007");
+    assert_eq!(render("{id:2}", &context(1234)), "1234");
+}
+
+#[test]
+fn formats_the_capture_time() {
+    assert_eq!(render("{time}", &context(1)), "20240309-140507");
+    assert_eq!(render("shot-{time:%H%M}", &context(1)), "shot-1405");
+    assert!("{time:%Q}".parse::<FileNameTemplate>().is_err());
+}
+
+#[test]
+fn sanitizes_the_substituted_names() {
+    let context = FileNameContext {
+        monitor: Some("HDMI/1"),
+        hostname: "lab\\desk",
+        ..context(1)
+    };
+    assert_eq!(
+        render("{hostname}-{monitor}-{width}x{height}", &context),
+        "lab_desk-HDMI_1-1920x1080"
+    );
+    assert_eq!(
+        render("{time:%D}", &context),
+        "03_09_24",
+        "strftime output is sanitized too"
+    );
+    assert_eq!(
+        render(
+            "{monitor}",
+            &FileNameContext {
+                monitor: None,
+                ..context
+            }
+        ),
+        "unknown"
+    );
+    assert!("shots/{id}".parse::<FileNameTemplate>().is_err());
+}
+
+#[test]
+fn parses_back_the_rendered_sequence_number() {
+    let templates = [
+        "{id}",
+        "{id:5}",
+        "shot-{id}",
+        "{hostname}-{id:3}-{monitor}",
+        "{time}-{id}",
+        "{width}x{height}-{id}",
+    ];
+
+    for template in templates {
+        let parsed = template.parse::<FileNameTemplate>().unwrap();
+        assert_eq!(
+            parsed.to_string().parse::<FileNameTemplate>(),
+            Ok(parsed.clone())
+        );
+
+        for id in [0, 7, 42, 12345] {
+            let file_name = parsed.render(&context(id));
+            assert_eq!(
+                parsed.parse_id(&file_name),
+                Some(id),
+                "{template} as {file_name}"
+            );
+        }
+    }
+
+    let parsed = "shot-{id}".parse::<FileNameTemplate>().unwrap();
+    assert_eq!(parsed.parse_id("thumbnail-3"), None);
+    assert_eq!(parsed.parse_id("shot-3a"), None);
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use common::TestFrame;
+use remotia::traits::FrameProcessor;
+use screen_snapper::{
+    overlay::{Color, OverlayPosition, TextOverlay},
+    pixel_format::{FrameFormat, PixelFormat},
+};
+
+const SIZE: u32 = 16;
+const PADDING: usize = 3;
+const UNTOUCHED: u8 = 0x11;
+const RED: [u8; 3] = [255, 0, 0];
+const BLUE: [u8; 3] = [0, 0, 255];
+
+/// Color expected at a pixel once "!" is drawn in the top-left corner at scale 1
+fn expected(x: u32, y: u32) -> Option<[u8; 3]> {
+    // The glyph is drawn past the box padding, its middle column lit but for the sixth row
+    let in_glyph = x == 2 + 2 && (2..2 + 7).contains(&y) && y != 2 + 5;
+    // 5 pixels wide and 7 high, with 2 pixels of padding around
+    let in_box = x < 9 && y < 11;
+
+    match (in_glyph, in_box) {
+        (true, _) => Some(RED),
+        (false, true) => Some(BLUE),
+        (false, false) => None,
+    }
+}
+
+async fn draw(format: FrameFormat) -> TestFrame {
+    let mut overlay = TextOverlay::builder()
+        .buffer_key(())
+        .text("!".parse().unwrap())
+        .position(OverlayPosition::At(0, 0))
+        .color(Color(RED))
+        .background(Color(BLUE))
+        .scale(1)
+        .build();
+
+    let frame = TestFrame::new(format, &vec![UNTOUCHED; format.buffer_size()]);
+    let frame = overlay.process(frame).await.unwrap();
+    assert_eq!(frame.error, None);
+    frame
+}
+
+async fn check(format: FrameFormat) {
+    let frame = draw(format).await;
+    let bpp = format.pixel_format.bytes_per_pixel();
+
+    for (y, row) in frame.pixels().chunks(format.stride).enumerate() {
+        assert!(
+            row[format.row_size()..]
+                .iter()
+                .all(|&byte| byte == UNTOUCHED),
+            "padding of row {y} written"
+        );
+
+        for (x, pixel) in row[..format.row_size()].chunks(bpp).enumerate() {
+            let (x, y) = (x as u32, y as u32);
+            match expected(x, y) {
+                Some(color) => {
+                    assert_eq!(format.pixel_format.read(pixel), color, "({x}, {y})");
+                    if format.pixel_format.has_alpha() {
+                        assert_eq!(pixel[3], 255, "alpha at ({x}, {y})");
+                    }
+                }
+                None => assert!(
+                    pixel.iter().all(|&byte| byte == UNTOUCHED),
+                    "({x}, {y}) written"
+                ),
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn draws_on_rgb_frames() {
+    check(FrameFormat::packed(PixelFormat::Rgb8, SIZE, SIZE)).await;
+}
+
+#[tokio::test]
+async fn draws_on_frames_with_alpha() {
+    check(FrameFormat::packed(PixelFormat::Rgba8, SIZE, SIZE)).await;
+    check(FrameFormat::packed(PixelFormat::Bgra8, SIZE, SIZE)).await;
+
+    // Channels are swapped, and not only read that way
+    let frame = draw(FrameFormat::packed(PixelFormat::Bgra8, SIZE, SIZE)).await;
+    let glyph_pixel = (2 * SIZE as usize + 4) * 4;
+    assert_eq!(
+        frame.pixels()[glyph_pixel..glyph_pixel + 4],
+        [0, 0, 255, 255]
+    );
+}
+
+#[tokio::test]
+async fn draws_on_padded_frames() {
+    for pixel_format in [PixelFormat::Rgb8, PixelFormat::Bgra8] {
+        let packed = FrameFormat::packed(pixel_format, SIZE, SIZE);
+        check(FrameFormat {
+            stride: packed.stride + PADDING,
+            ..packed
+        })
+        .await;
+    }
+}
@@ -0,0 +1,94 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono::Local;
+use screen_snapper::{
+    retention::{RetentionEnforcer, RetentionPolicy},
+    storage::{MemorySink, StorageSink},
+};
+
+/// Stores a snapshot and lists it in the index, as the saver does
+fn save(sink: &MemorySink, key: &str, age: Duration) {
+    sink.put(key, &[0; 10]).unwrap();
+
+    let time = Local::now() - age;
+    let line = format!(
+        "{{\"file\":\"{key}\",\"time\":\"{}\"}}\n",
+        time.to_rfc3339()
+    );
+    sink.append("index.jsonl", line.as_bytes()).unwrap();
+}
+
+fn enforcer(sink: &MemorySink, policy: RetentionPolicy) -> RetentionEnforcer {
+    RetentionEnforcer::builder()
+        .sink(Arc::new(sink.clone()))
+        .policy(policy)
+        .build()
+}
+
+#[test]
+fn deletes_the_oldest_recorded_snapshots() {
+    let sink = MemorySink::new();
+    save(&sink, "shot-1.png", Duration::from_secs(30));
+    save(&sink, "shot-2.png", Duration::from_secs(20));
+    save(&sink, "shot-3.png", Duration::from_secs(10));
+
+    let enforcer = enforcer(
+        &sink,
+        RetentionPolicy {
+            max_count: Some(2),
+            ..Default::default()
+        },
+    );
+    assert_eq!(enforcer.enforce().unwrap(), 1);
+    assert!(sink.get("shot-1.png").is_none());
+
+    // Only the snapshots indexed since are read
+    save(&sink, "shot-4.png", Duration::ZERO);
+    assert_eq!(enforcer.enforce().unwrap(), 1);
+    assert!(sink.get("shot-2.png").is_none());
+    assert!(sink.get("shot-3.png").is_some());
+    assert!(sink.get("shot-4.png").is_some());
+}
+
+#[test]
+fn leaves_objects_not_saved_by_the_saver() {
+    let sink = MemorySink::new();
+    sink.put("shot-notes.png", &[0; 100]).unwrap();
+    save(&sink, "shot-1.png", Duration::from_secs(3600));
+
+    let enforcer = enforcer(
+        &sink,
+        RetentionPolicy {
+            max_count: Some(0),
+            max_bytes: Some(0),
+            max_age: Some(Duration::from_secs(60)),
+        },
+    );
+    assert_eq!(enforcer.enforce().unwrap(), 1);
+
+    assert!(sink.get("shot-1.png").is_none());
+    assert!(sink.get("shot-notes.png").is_some());
+    assert!(sink.get("index.jsonl").is_some());
+}
+
+#[test]
+fn limits_the_bytes_and_age_of_the_snapshots() {
+    let sink = MemorySink::new();
+    save(&sink, "1.png", Duration::from_secs(7200));
+    save(&sink, "2.png", Duration::from_secs(60));
+    save(&sink, "3.png", Duration::from_secs(30));
+    save(&sink, "4.png", Duration::ZERO);
+
+    let enforcer = enforcer(
+        &sink,
+        RetentionPolicy {
+            max_bytes: Some(25),
+            max_age: Some(Duration::from_secs(3600)),
+            ..Default::default()
+        },
+    );
+    assert_eq!(enforcer.enforce().unwrap(), 2);
+
+    assert!(sink.get("2.png").is_none());
+    assert!(sink.get("3.png").is_some());
+}
@@ -1,12 +1,11 @@
 use std::time::Duration;
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use remotia::profilation::loggers::console::ConsoleAverageStatsLogger;
 use remotia::profilation::time::diff::TimestampDiffCalculator;
 use remotia::serialization::bincode::BincodeSerializer;
 use remotia::{
     buffers::pool_registry::PoolRegistry,
-    capture::scrap::ScrapFrameCapturer,
     pipeline::{component::Component, registry::PipelineRegistry, Pipeline},
     processors::{error_switch::OnErrorSwitch, functional::Function, ticker::Ticker},
     profilation::time::add::TimestampAdder,
@@ -18,10 +17,25 @@ use remotia_srt::{
     sender::SRTFrameSender,
     srt_tokio::{options::ByteCount, SrtSocket},
 };
-use screen_stream::types::{BufferType::*, FrameData, Stat::*};
+use screen_stream::{
+    codec_format::av_pixel_format,
+    geometry::FrameRescaler,
+    overlay::{Color, OverlayPosition, OverlayText, TextOverlay, DEFAULT_OVERLAY_SCALE},
+    pattern_capturer::{Pattern, PatternCapturer, PatternResize},
+    pixel_format::{FrameFormat, PixelFormat, PixelFormatConverter},
+    redaction::{RedactionArea, RedactionStyle, Redactor},
+    scrap_capturer::ScrapCapturer,
+    types::{BufferType::*, FrameData, Stat::*},
+};
 
 use remotia::register;
 
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Source {
+    Screen,
+    Pattern,
+}
+
 #[derive(Parser, Debug)]
 struct Args {
     #[arg(short, long, default_value_t = 60)]
@@ -41,6 +55,51 @@ struct Args {
 
     #[arg(id = "codec-option", long)]
     codec_options: Vec<String>,
+
+    #[arg(long, value_enum, default_value_t = Source::Screen)]
+    source: Source,
+
+    #[arg(long, default_value = "smpte-bars")]
+    pattern: Pattern,
+
+    #[arg(long, default_value_t = 1280)]
+    pattern_width: u32,
+
+    #[arg(long, default_value_t = 720)]
+    pattern_height: u32,
+
+    /// Switch the pattern to this WIDTHxHEIGHT and back every FRAMES frames, as WIDTHxHEIGHT@FRAMES
+    #[arg(long)]
+    pattern_resize: Option<PatternResize>,
+
+    /// Hide this WIDTHxHEIGHT+X+Y rectangle before encoding, can be repeated
+    #[arg(long)]
+    redact: Vec<RedactionArea>,
+
+    /// How to hide the redacted areas: black, pixelate[:SIZE] or blur[:RADIUS]
+    #[arg(long, default_value = "black")]
+    redact_style: RedactionStyle,
+
+    /// Burn this text into the stream. Placeholders: {time} or {time:FORMAT}
+    /// (strftime-style), {hostname} and {monitor}, \n starting a new line
+    #[arg(long)]
+    overlay: Option<OverlayText>,
+
+    /// Where to draw the overlay: top-left, top-right, bottom-left, bottom-right or X,Y
+    #[arg(long, default_value = "bottom-right")]
+    overlay_position: OverlayPosition,
+
+    /// Overlay text color, as a name or #RRGGBB
+    #[arg(long, default_value = "white")]
+    overlay_color: Color,
+
+    /// Color of the box behind the overlay text, none by default
+    #[arg(long)]
+    overlay_background: Option<Color>,
+
+    /// Size of the overlay font pixels, in frame pixels
+    #[arg(long, default_value_t = DEFAULT_OVERLAY_SCALE)]
+    overlay_scale: u32,
 }
 
 #[derive(PartialEq, Eq, Hash)]
@@ -58,12 +117,43 @@ async fn main() {
 
     let args = Args::parse();
 
-    let capturer = ScrapFrameCapturer::new_from_primary(CapturedRGBAFrameBuffer);
+    // Scrap only captures the primary display
+    let scrap_monitor = match args.source {
+        Source::Screen => Some("primary"),
+        Source::Pattern => None,
+    };
 
-    log::info!("Streaming at {}x{}", capturer.width(), capturer.height());
+    let mut scrap_capturer = match args.source {
+        Source::Screen => Some(
+            ScrapCapturer::builder()
+                .buffer_key(CapturedRGBAFrameBuffer)
+                .build(),
+        ),
+        Source::Pattern => None,
+    };
+
+    // Scrap captures BGRA frames, whose rows may be padded
+    let captured_format = match &mut scrap_capturer {
+        Some(capturer) => capturer
+            .open()
+            .expect("Couldn't capture the primary display"),
+        None => FrameFormat::packed(PixelFormat::Rgba8, args.pattern_width, args.pattern_height),
+    };
 
-    let width = capturer.width() as u32;
-    let height = capturer.height() as u32;
+    // Frames are packed before being encoded
+    let encoded_format = FrameFormat::packed(
+        captured_format.pixel_format,
+        captured_format.width,
+        captured_format.height,
+    );
+    let (width, height) = (encoded_format.width, encoded_format.height);
+
+    log::info!(
+        "Streaming at {}x{} ({})",
+        width,
+        height,
+        encoded_format.pixel_format
+    );
 
     let stream_width = args.stream_width.unwrap_or(width);
     let stream_height = args.stream_height.unwrap_or(height);
@@ -71,7 +161,11 @@ async fn main() {
     let mut pools = PoolRegistry::new();
     let pixels_count = (width * height) as usize;
     pools
-        .register(CapturedRGBAFrameBuffer, POOLS_SIZE, pixels_count * 4)
+        .register(
+            CapturedRGBAFrameBuffer,
+            POOLS_SIZE,
+            captured_format.buffer_size(),
+        )
         .await;
     pools
         .register(EncodedFrameBuffer, POOLS_SIZE, pixels_count * 4)
@@ -97,7 +191,7 @@ async fn main() {
                 .input_height(height as i32)
                 .output_width(stream_width as i32)
                 .output_height(stream_height as i32)
-                .input_pixel_format(ffi::AVPixelFormat_AV_PIX_FMT_RGBA)
+                .input_pixel_format(av_pixel_format(encoded_format.pixel_format))
                 .output_pixel_format(ffi::AVPixelFormat_AV_PIX_FMT_YUV420P)
                 .build(),
         )
@@ -129,16 +223,85 @@ async fn main() {
         .await
         .unwrap();
 
+    let capture_component = Component::new()
+        .append(Ticker::new(1000 / args.framerate))
+        .append(pools.get(CapturedRGBAFrameBuffer).borrower())
+        .append(TimestampAdder::new(CaptureTime));
+
+    let capture_component = match scrap_capturer {
+        // Frames which cannot be converted give their buffer back through the error pipeline
+        Some(capturer) => capture_component
+            .append(capturer)
+            .append(
+                PixelFormatConverter::builder()
+                    .buffer_key(CapturedRGBAFrameBuffer)
+                    .target(encoded_format.pixel_format)
+                    .build(),
+            )
+            .append(OnErrorSwitch::new(pipelines.get_mut(&Pipelines::Error))),
+        None => capture_component.append(
+            PatternCapturer::builder()
+                .buffer_key(CapturedRGBAFrameBuffer)
+                .pattern(args.pattern)
+                .pixel_format(captured_format.pixel_format)
+                .width(width)
+                .height(height)
+                .maybe_resize(args.pattern_resize)
+                .build(),
+        ),
+    };
+
+    // Letterboxed rather than reinitialising the encoder: the client decodes and renders
+    // the stream at the fixed size given on its command line, which nothing in the stream
+    // could update, so the stream keeps the size of the first frames
+    let capture_component = capture_component
+        .append(
+            FrameRescaler::builder()
+                .buffer_key(CapturedRGBAFrameBuffer)
+                .width(width)
+                .height(height)
+                .build(),
+        )
+        .append(OnErrorSwitch::new(pipelines.get_mut(&Pipelines::Error)));
+
+    // Frames which cannot be redacted never reach the encoder
+    let capture_component = if args.redact.is_empty() {
+        capture_component
+    } else {
+        capture_component
+            .append(
+                Redactor::builder()
+                    .buffer_key(CapturedRGBAFrameBuffer)
+                    .areas(args.redact.clone())
+                    .style(args.redact_style)
+                    .build(),
+            )
+            .append(OnErrorSwitch::new(pipelines.get_mut(&Pipelines::Error)))
+    };
+
+    let capture_component = match args.overlay.clone() {
+        Some(text) => capture_component
+            .append(
+                TextOverlay::builder()
+                    .buffer_key(CapturedRGBAFrameBuffer)
+                    .text(text)
+                    .maybe_monitor(scrap_monitor)
+                    .position(args.overlay_position)
+                    .color(args.overlay_color)
+                    .maybe_background(args.overlay_background)
+                    .scale(args.overlay_scale)
+                    .build(),
+            )
+            .append(OnErrorSwitch::new(pipelines.get_mut(&Pipelines::Error))),
+        None => capture_component,
+    };
+
     register!(
         pipelines,
         Pipelines::Main,
         Pipeline::<FrameData>::new()
             .link(
-                Component::new()
-                    .append(Ticker::new(1000 / args.framerate))
-                    .append(pools.get(CapturedRGBAFrameBuffer).borrower())
-                    .append(TimestampAdder::new(CaptureTime))
-                    .append(capturer)
+                capture_component
                     .append(TimestampAdder::new(EncodePushTime))
                     .append(encoder_pusher),
             )
@@ -0,0 +1,13 @@
+use remotia_ffmpeg_codecs::ffi;
+
+use crate::pixel_format::PixelFormat;
+
+/// FFmpeg pixel format matching the layout of the given raw buffers
+pub fn av_pixel_format(pixel_format: PixelFormat) -> ffi::AVPixelFormat {
+    match pixel_format {
+        PixelFormat::Rgb8 => ffi::AVPixelFormat_AV_PIX_FMT_RGB24,
+        PixelFormat::Bgr8 => ffi::AVPixelFormat_AV_PIX_FMT_BGR24,
+        PixelFormat::Rgba8 => ffi::AVPixelFormat_AV_PIX_FMT_RGBA,
+        PixelFormat::Bgra8 => ffi::AVPixelFormat_AV_PIX_FMT_BGRA,
+    }
+}
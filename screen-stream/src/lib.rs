@@ -1 +1,8 @@
+pub mod codec_format;
+pub mod geometry;
+pub mod overlay;
+pub mod pattern_capturer;
+pub mod pixel_format;
+pub mod redaction;
+pub mod scrap_capturer;
 pub mod types;
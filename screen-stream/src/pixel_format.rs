@@ -0,0 +1,215 @@
+use std::{fmt::Display, str::FromStr};
+
+use async_trait::async_trait;
+use bon::Builder;
+use remotia::{
+    buffers::BytesMut,
+    traits::{BorrowMutFrameProperties, FrameError, FrameProcessor, FrameProperties},
+};
+
+use crate::types::Error;
+
+/// Channel order and depth of a raw frame buffer
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PixelFormat {
+    #[default]
+    Rgb8,
+    Bgr8,
+    Rgba8,
+    Bgra8,
+}
+
+impl PixelFormat {
+    pub fn bytes_per_pixel(&self) -> usize {
+        match self {
+            Self::Rgb8 | Self::Bgr8 => 3,
+            Self::Rgba8 | Self::Bgra8 => 4,
+        }
+    }
+
+    pub fn has_alpha(&self) -> bool {
+        matches!(self, Self::Rgba8 | Self::Bgra8)
+    }
+
+    /// Reads the color channels of a pixel, ignoring alpha
+    pub fn read(&self, pixel: &[u8]) -> [u8; 3] {
+        match self {
+            Self::Rgb8 | Self::Rgba8 => [pixel[0], pixel[1], pixel[2]],
+            Self::Bgr8 | Self::Bgra8 => [pixel[2], pixel[1], pixel[0]],
+        }
+    }
+
+    /// Writes an opaque pixel
+    pub fn write(&self, pixel: &mut [u8], [r, g, b]: [u8; 3]) {
+        match self {
+            Self::Rgb8 => pixel.copy_from_slice(&[r, g, b]),
+            Self::Bgr8 => pixel.copy_from_slice(&[b, g, r]),
+            Self::Rgba8 => pixel.copy_from_slice(&[r, g, b, 255]),
+            Self::Bgra8 => pixel.copy_from_slice(&[b, g, r, 255]),
+        }
+    }
+}
+
+impl Display for PixelFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Rgb8 => write!(f, "rgb8"),
+            Self::Bgr8 => write!(f, "bgr8"),
+            Self::Rgba8 => write!(f, "rgba8"),
+            Self::Bgra8 => write!(f, "bgra8"),
+        }
+    }
+}
+
+impl FromStr for PixelFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "rgb" | "rgb8" => Ok(Self::Rgb8),
+            "bgr" | "bgr8" => Ok(Self::Bgr8),
+            "rgba" | "rgba8" => Ok(Self::Rgba8),
+            "bgra" | "bgra8" => Ok(Self::Bgra8),
+            _ => Err(format!("Unknown pixel format: {value}")),
+        }
+    }
+}
+
+/// Layout of a frame buffer, stored in the frame data next to the buffer it describes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FrameFormat {
+    pub pixel_format: PixelFormat,
+    pub width: u32,
+    pub height: u32,
+
+    /// Bytes from the start of a row to the start of the next one, padding included
+    pub stride: usize,
+}
+
+impl FrameFormat {
+    /// Format of a buffer whose rows are not padded
+    pub fn packed(pixel_format: PixelFormat, width: u32, height: u32) -> Self {
+        Self {
+            pixel_format,
+            width,
+            height,
+            stride: width as usize * pixel_format.bytes_per_pixel(),
+        }
+    }
+
+    pub fn row_size(&self) -> usize {
+        self.width as usize * self.pixel_format.bytes_per_pixel()
+    }
+
+    pub fn buffer_size(&self) -> usize {
+        self.stride * self.height as usize
+    }
+
+    pub fn is_packed(&self) -> bool {
+        self.stride == self.row_size()
+    }
+
+    /// Whether the buffer holds exactly one frame in this format
+    pub fn fits(&self, buffer: &[u8]) -> bool {
+        self.stride >= self.row_size() && buffer.len() == self.buffer_size()
+    }
+
+    /// Rows of the frame, without their padding
+    pub fn rows<'a>(&self, buffer: &'a [u8]) -> impl Iterator<Item = &'a [u8]> {
+        let row_size = self.row_size();
+        buffer
+            .chunks(self.stride.max(1))
+            .take(self.height as usize)
+            .map(move |row| &row[..row_size])
+    }
+
+    /// Copies a buffer which fits this format into a packed buffer of the target pixel format
+    pub fn convert(&self, buffer: &[u8], target: PixelFormat) -> Vec<u8> {
+        if self.pixel_format == target && self.is_packed() {
+            return buffer.to_vec();
+        }
+
+        let source_bpp = self.pixel_format.bytes_per_pixel();
+        let target_bpp = target.bytes_per_pixel();
+
+        let mut output = vec![0; self.width as usize * self.height as usize * target_bpp];
+        let pixels = self
+            .rows(buffer)
+            .flat_map(|row| row.chunks_exact(source_bpp));
+        for (pixel, output_pixel) in pixels.zip(output.chunks_exact_mut(target_bpp)) {
+            target.write(output_pixel, self.pixel_format.read(pixel));
+        }
+
+        output
+    }
+}
+
+/// Declares the format of a buffer filled by a processor which does not do it by itself
+#[derive(Builder)]
+pub struct FrameFormatAdder<K> {
+    buffer_key: K,
+    format: FrameFormat,
+}
+
+#[async_trait]
+impl<K, F> FrameProcessor<F> for FrameFormatAdder<K>
+where
+    F: Send + 'static,
+    K: Send + Copy,
+    F: FrameProperties<K, FrameFormat>,
+{
+    async fn process(&mut self, mut dto: F) -> Option<F> {
+        dto.set(self.buffer_key, self.format);
+        Some(dto)
+    }
+}
+
+/// Converts a buffer to another pixel format in place, reporting the frames which do not match their declared format
+#[derive(Builder)]
+pub struct PixelFormatConverter<K> {
+    buffer_key: K,
+    target: PixelFormat,
+}
+
+#[async_trait]
+impl<K, F> FrameProcessor<F> for PixelFormatConverter<K>
+where
+    F: Send + 'static,
+    K: Send + Copy,
+    F: BorrowMutFrameProperties<K, BytesMut> + FrameProperties<K, FrameFormat> + FrameError<Error>,
+{
+    async fn process(&mut self, mut dto: F) -> Option<F> {
+        let Some(format) = dto.get(&self.buffer_key) else {
+            log::warn!("No format declared for the buffer");
+            dto.report_error(Error::MissingFormat);
+            return Some(dto);
+        };
+
+        let target_format = FrameFormat::packed(self.target, format.width, format.height);
+        if format == target_format {
+            return Some(dto);
+        }
+
+        let Some(buffer) = dto.get_mut_ref(&self.buffer_key) else {
+            dto.report_error(Error::MissingBuffer);
+            return Some(dto);
+        };
+
+        if !format.fits(buffer) {
+            log::warn!(
+                "Buffer of {} bytes does not match {:?}",
+                buffer.len(),
+                format
+            );
+            dto.report_error(Error::SizeMismatch);
+            return Some(dto);
+        }
+
+        let converted = format.convert(buffer, self.target);
+        buffer.clear();
+        buffer.extend_from_slice(&converted);
+
+        dto.set(self.buffer_key, target_format);
+        Some(dto)
+    }
+}
@@ -0,0 +1,281 @@
+use std::{fmt::Display, str::FromStr};
+
+use async_trait::async_trait;
+use bon::Builder;
+use remotia::{
+    buffers::BytesMut,
+    traits::{BorrowMutFrameProperties, FrameError, FrameProcessor, FrameProperties},
+};
+
+use crate::{pixel_format::FrameFormat, types::Error};
+
+pub const DEFAULT_PIXELATE_BLOCK_SIZE: u32 = 16;
+pub const DEFAULT_BLUR_RADIUS: u32 = 12;
+
+/// Box blurs applied in a row, approximating a gaussian blur
+const BLUR_PASSES: usize = 3;
+
+/// Rectangle to hide, relative to the top-left corner of the frame.
+/// It may lie partially, or entirely, outside of the frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RedactionArea {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl RedactionArea {
+    /// Part of the area within a frame, as (x, y, width, height)
+    fn clip(&self, width: u32, height: u32) -> Option<(usize, usize, usize, usize)> {
+        let clamp = |value: i64, max: u32| value.clamp(0, max as i64) as usize;
+
+        let left = clamp(self.x as i64, width);
+        let top = clamp(self.y as i64, height);
+        let right = clamp(self.x as i64 + self.width as i64, width);
+        let bottom = clamp(self.y as i64 + self.height as i64, height);
+
+        (right > left && bottom > top).then_some((left, top, right - left, bottom - top))
+    }
+}
+
+impl Display for RedactionArea {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}{:+}{:+}", self.width, self.height, self.x, self.y)
+    }
+}
+
+/// Parses "WIDTHxHEIGHT+X+Y" geometries, the offsets being possibly negative
+impl FromStr for RedactionArea {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid area (expected WIDTHxHEIGHT+X+Y): {value}");
+
+        let split = value.find(['+', '-']).ok_or_else(invalid)?;
+        let (size, offset) = value.split_at(split);
+        let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+
+        // The second offset starts at the next sign
+        let split = offset[1..].find(['+', '-']).ok_or_else(invalid)? + 1;
+        let (x, y) = offset.split_at(split);
+
+        let area = Self {
+            x: x.parse().map_err(|_| invalid())?,
+            y: y.parse().map_err(|_| invalid())?,
+            width: width.parse().map_err(|_| invalid())?,
+            height: height.parse().map_err(|_| invalid())?,
+        };
+
+        if area.width == 0 || area.height == 0 {
+            return Err(format!("Empty area: {value}"));
+        }
+
+        Ok(area)
+    }
+}
+
+/// How the areas are hidden
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RedactionStyle {
+    #[default]
+    Black,
+    /// Averages blocks of the given size
+    Pixelate(u32),
+    /// Blurs with the given radius
+    Blur(u32),
+}
+
+/// Parses "black", "pixelate", "pixelate:<block size>", "blur" or "blur:<radius>"
+impl FromStr for RedactionStyle {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (name, size) = match value.split_once(':') {
+            Some((name, size)) => {
+                let size = size
+                    .parse()
+                    .ok()
+                    .filter(|size| *size > 0)
+                    .ok_or_else(|| format!("Invalid redaction size: {size}"))?;
+                (name, Some(size))
+            }
+            None => (value, None),
+        };
+
+        match (name, size) {
+            ("black", None) => Ok(Self::Black),
+            ("pixelate", size) => Ok(Self::Pixelate(size.unwrap_or(DEFAULT_PIXELATE_BLOCK_SIZE))),
+            ("blur", size) => Ok(Self::Blur(size.unwrap_or(DEFAULT_BLUR_RADIUS))),
+            _ => Err(format!("Unknown redaction style: {value}")),
+        }
+    }
+}
+
+/// Source of areas which move between frames, e.g. windows
+pub trait AreaLocator: Send {
+    /// Areas to hide in the next frame
+    fn locate(&mut self) -> Result<Vec<RedactionArea>, Error>;
+}
+
+/// Hides areas of a frame in place, whatever its pixel format
+pub fn redact(format: FrameFormat, buffer: &mut [u8], area: RedactionArea, style: RedactionStyle) {
+    let Some((x, y, width, height)) = area.clip(format.width, format.height) else {
+        return;
+    };
+
+    let pixel_format = format.pixel_format;
+    let bpp = pixel_format.bytes_per_pixel();
+    let offset = |column: usize, row: usize| row * format.stride + column * bpp;
+
+    match style {
+        RedactionStyle::Black => {
+            for row in y..y + height {
+                for column in x..x + width {
+                    let offset = offset(column, row);
+                    pixel_format.write(&mut buffer[offset..offset + bpp], [0; 3]);
+                }
+            }
+        }
+        RedactionStyle::Pixelate(block_size) => {
+            let block_size = block_size.max(1) as usize;
+
+            for block_y in (y..y + height).step_by(block_size) {
+                for block_x in (x..x + width).step_by(block_size) {
+                    let rows = block_y..(block_y + block_size).min(y + height);
+                    let columns = block_x..(block_x + block_size).min(x + width);
+
+                    let mut sum = [0u64; 3];
+                    for row in rows.clone() {
+                        for column in columns.clone() {
+                            let offset = offset(column, row);
+                            let color = pixel_format.read(&buffer[offset..offset + bpp]);
+                            for (sum, channel) in sum.iter_mut().zip(color) {
+                                *sum += channel as u64;
+                            }
+                        }
+                    }
+
+                    let count = (rows.len() * columns.len()) as u64;
+                    let average = sum.map(|sum| (sum / count) as u8);
+
+                    for row in rows.clone() {
+                        for column in columns.clone() {
+                            let offset = offset(column, row);
+                            pixel_format.write(&mut buffer[offset..offset + bpp], average);
+                        }
+                    }
+                }
+            }
+        }
+        RedactionStyle::Blur(radius) => {
+            let mut pixels: Vec<[u8; 3]> = (y..y + height)
+                .flat_map(|row| (x..x + width).map(move |column| (column, row)))
+                .map(|(column, row)| {
+                    let offset = offset(column, row);
+                    pixel_format.read(&buffer[offset..offset + bpp])
+                })
+                .collect();
+
+            for _ in 0..BLUR_PASSES {
+                for row in 0..height {
+                    box_blur(&mut pixels, row * width, width, 1, radius as usize);
+                }
+                for column in 0..width {
+                    box_blur(&mut pixels, column, height, width, radius as usize);
+                }
+            }
+
+            for (index, color) in pixels.into_iter().enumerate() {
+                let offset = offset(x + index % width, y + index / width);
+                pixel_format.write(&mut buffer[offset..offset + bpp], color);
+            }
+        }
+    }
+}
+
+/// Averages each pixel of a line with its neighbours within the radius, the line being
+/// made of `count` pixels starting at `start`, `step` pixels apart
+fn box_blur(pixels: &mut [[u8; 3]], start: usize, count: usize, step: usize, radius: usize) {
+    let mut prefix_sums = vec![[0u32; 3]; count + 1];
+    for index in 0..count {
+        let color = pixels[start + index * step];
+        for channel in 0..3 {
+            prefix_sums[index + 1][channel] = prefix_sums[index][channel] + color[channel] as u32;
+        }
+    }
+
+    for index in 0..count {
+        let low = index.saturating_sub(radius);
+        let high = (index + radius + 1).min(count);
+        let length = (high - low) as u32;
+
+        pixels[start + index * step] = [0, 1, 2].map(|channel| {
+            ((prefix_sums[high][channel] - prefix_sums[low][channel]) / length) as u8
+        });
+    }
+}
+
+/// Hides fixed and located areas of a buffer in place.
+/// Frames which cannot be redacted are reported as errors rather than let through.
+#[derive(Builder)]
+pub struct Redactor<K> {
+    buffer_key: K,
+
+    #[builder(default)]
+    areas: Vec<RedactionArea>,
+
+    /// Looked up before each frame
+    #[builder(default)]
+    locators: Vec<Box<dyn AreaLocator>>,
+
+    #[builder(default)]
+    style: RedactionStyle,
+}
+
+#[async_trait]
+impl<K, F> FrameProcessor<F> for Redactor<K>
+where
+    F: Send + 'static,
+    K: Send + Copy,
+    F: BorrowMutFrameProperties<K, BytesMut> + FrameProperties<K, FrameFormat> + FrameError<Error>,
+{
+    async fn process(&mut self, mut dto: F) -> Option<F> {
+        let Some(format) = dto.get(&self.buffer_key) else {
+            dto.report_error(Error::MissingFormat);
+            return Some(dto);
+        };
+
+        let mut areas = self.areas.clone();
+        for locator in &mut self.locators {
+            match locator.locate() {
+                Ok(located) => areas.extend(located),
+                Err(error) => {
+                    dto.report_error(error);
+                    return Some(dto);
+                }
+            }
+        }
+
+        let Some(buffer) = dto.get_mut_ref(&self.buffer_key) else {
+            dto.report_error(Error::MissingBuffer);
+            return Some(dto);
+        };
+
+        if !format.fits(buffer) {
+            log::warn!(
+                "Buffer of {} bytes does not match {:?}, unable to redact it",
+                buffer.len(),
+                format
+            );
+            dto.report_error(Error::SizeMismatch);
+            return Some(dto);
+        }
+
+        for area in areas {
+            redact(format, buffer, area, self.style);
+        }
+
+        Some(dto)
+    }
+}
@@ -0,0 +1,161 @@
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use bon::Builder;
+use remotia::{
+    buffers::BytesMut,
+    traits::{BorrowMutFrameProperties, FrameProcessor, FrameProperties},
+};
+use scrap::{Capturer, Display};
+
+use crate::{
+    geometry::{GeometryChange, GeometryTracker},
+    pixel_format::{FrameFormat, PixelFormat},
+};
+
+pub const DEFAULT_DISPLAY_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Delay between two polls of a capturer which has no new frame yet
+const POLL_INTERVAL: Duration = Duration::from_millis(2);
+
+/// Delay before reopening the display after a failed capture, e.g. while it is unplugged
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Captures the primary display with Scrap, declaring the format of its BGRA frames.
+/// The display is reopened when its resolution changes or after a failed capture,
+/// so that the frames follow it rather than keeping the size it had at startup.
+#[derive(Builder)]
+pub struct ScrapCapturer<K> {
+    #[builder(skip)]
+    capturer: Option<Capturer>,
+
+    #[builder(skip)]
+    display_size: (usize, usize),
+
+    #[builder(skip)]
+    checked_at: Option<Instant>,
+
+    #[builder(skip)]
+    tracker: GeometryTracker,
+
+    buffer_key: K,
+
+    /// How often the resolution of the primary display is checked
+    #[builder(default = DEFAULT_DISPLAY_CHECK_INTERVAL)]
+    check_interval: Duration,
+}
+
+// As for remotia's ScrapFrameCapturer, the capturer is only used by the task running the
+// processor, which may be moved to another thread by multi-threaded pipelines
+unsafe impl<K: Send> Send for ScrapCapturer<K> {}
+
+impl<K> ScrapCapturer<K> {
+    /// Opens the primary display ahead of the first capture, returning the format of its frames.
+    /// A first frame of another format than the one opened is declared as a geometry change.
+    pub fn open(&mut self) -> io::Result<FrameFormat> {
+        let mut buffer = BytesMut::new();
+        loop {
+            match self.capture(&mut buffer) {
+                Ok(format) => {
+                    self.tracker.announce(format);
+                    return Ok(format);
+                }
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(POLL_INTERVAL)
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    fn check_display(&mut self) -> io::Result<()> {
+        let display = Display::primary()?;
+        let size = (display.width(), display.height());
+        self.checked_at = Some(Instant::now());
+
+        if self.capturer.is_some() {
+            if size == self.display_size {
+                return Ok(());
+            }
+
+            log::info!(
+                "Primary display resized from {}x{} to {}x{}, reopening it",
+                self.display_size.0,
+                self.display_size.1,
+                size.0,
+                size.1
+            );
+        }
+
+        // Some platforms do not allow two capturers of the same display
+        self.capturer = None;
+        self.capturer = Some(Capturer::new(display)?);
+        self.display_size = size;
+
+        Ok(())
+    }
+
+    fn capture(&mut self, buffer: &mut BytesMut) -> io::Result<FrameFormat> {
+        let check_due = match self.checked_at {
+            Some(checked_at) => checked_at.elapsed() >= self.check_interval,
+            None => true,
+        };
+        if self.capturer.is_none() || check_due {
+            self.check_display()?;
+        }
+
+        let capturer = self.capturer.as_mut().expect("Display opened above");
+        let (width, height) = (capturer.width(), capturer.height());
+
+        // Scrap frames may have padded rows
+        let frame = capturer.frame()?;
+        let stride = frame.len() / height.max(1);
+
+        buffer.clear();
+        buffer.extend_from_slice(&frame);
+
+        Ok(FrameFormat {
+            pixel_format: PixelFormat::Bgra8,
+            width: width as u32,
+            height: height as u32,
+            stride,
+        })
+    }
+}
+
+#[async_trait]
+impl<K, F> FrameProcessor<F> for ScrapCapturer<K>
+where
+    F: Send + 'static,
+    K: Send + Copy,
+    F: BorrowMutFrameProperties<K, BytesMut>
+        + FrameProperties<K, FrameFormat>
+        + FrameProperties<K, GeometryChange>,
+{
+    async fn process(&mut self, mut dto: F) -> Option<F> {
+        loop {
+            let buffer = dto
+                .get_mut_ref(&self.buffer_key)
+                .expect("No buffer to write to in frame data");
+
+            let delay = match self.capture(buffer) {
+                Ok(format) => {
+                    self.tracker.declare(&mut dto, self.buffer_key, format);
+                    return Some(dto);
+                }
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => POLL_INTERVAL,
+                Err(error) => {
+                    // Waits for the display to come back rather than failing the pipeline
+                    log::warn!("Could not capture the primary display, retrying: {}", error);
+                    self.capturer = None;
+                    RETRY_DELAY
+                }
+            };
+
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
@@ -14,6 +14,8 @@ use remotia::{
     },
 };
 
+use crate::{geometry::GeometryChange, pixel_format::FrameFormat};
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Encode, Decode)]
 pub enum BufferType {
     CapturedRGBAFrameBuffer,
@@ -42,12 +44,18 @@ pub enum Stat {
 pub enum Error {
     NoFrame,
     CodecError,
+
+    MissingBuffer,
+    MissingFormat,
+    SizeMismatch,
 }
 
 #[derive(Default, Debug)]
 pub struct FrameData {
     statistics: HashMap<Stat, u128>,
     buffers: HashMap<BufferType, BytesMut>,
+    formats: HashMap<BufferType, FrameFormat>,
+    geometry_changes: HashMap<BufferType, GeometryChange>,
     error: Option<Error>,
 }
 
@@ -80,6 +88,8 @@ impl Decode for FrameData {
         Ok(Self {
             statistics,
             buffers,
+            formats: HashMap::new(),
+            geometry_changes: HashMap::new(),
             error,
         })
     }
@@ -95,6 +105,26 @@ impl FrameProperties<Stat, u128> for FrameData {
     }
 }
 
+impl FrameProperties<BufferType, FrameFormat> for FrameData {
+    fn set(&mut self, key: BufferType, value: FrameFormat) {
+        self.formats.insert(key, value);
+    }
+
+    fn get(&self, key: &BufferType) -> Option<FrameFormat> {
+        self.formats.get(key).copied()
+    }
+}
+
+impl FrameProperties<BufferType, GeometryChange> for FrameData {
+    fn set(&mut self, key: BufferType, value: GeometryChange) {
+        self.geometry_changes.insert(key, value);
+    }
+
+    fn get(&self, key: &BufferType) -> Option<GeometryChange> {
+        self.geometry_changes.get(key).copied()
+    }
+}
+
 impl PullableFrameProperties<BufferType, BytesMut> for FrameData {
     fn push(&mut self, key: BufferType, value: BytesMut) {
         self.buffers.insert(key, value);